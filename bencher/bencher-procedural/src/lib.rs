@@ -1,23 +1,93 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{
+	parse2, parse_quote, spanned::Spanned, Attribute, Error, ImplItem, Item, ItemImpl, Result,
+};
 
 #[proc_macro_attribute]
-pub fn benchmarkable(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn benchmarkable(attr: TokenStream, item: TokenStream) -> TokenStream {
+	expand(attr.into(), item.into())
+		.unwrap_or_else(Error::into_compile_error)
+		.into()
+}
+
+#[proc_macro_attribute]
+pub fn skip(_attr: TokenStream, item: TokenStream) -> TokenStream {
+	Error::new_spanned(
+		TokenStream2::from(item),
+		"`#[skip]` can only be applied to methods of an impl block with `#[benchmarkable]`",
+	)
+	.into_compile_error()
+	.into()
+}
+
+fn expand(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+	if !attr.is_empty() {
+		return Err(Error::new_spanned(
+			attr,
+			"`#[benchmarkable]` does not take arguments",
+		));
+	}
+	if let Ok(Item::Impl(item)) = parse2(item.clone()) {
+		return Ok(expand_impl(item));
+	}
 	let syn::ItemFn {
 		attrs,
 		vis,
 		sig,
 		block,
-	} = syn::parse(item).unwrap();
-	(quote::quote! {
+	} = parse2(item.clone()).map_err(|_| {
+		Error::new_spanned(
+			item,
+			"`#[benchmarkable]` can only be applied to functions, methods and impl blocks",
+		)
+	})?;
+	if let Some(asyncness) = &sig.asyncness {
+		return Err(Error::new(
+			asyncness.span(),
+			"`#[benchmarkable]` cannot be applied to async functions",
+		));
+	}
+	Ok(quote::quote! {
 		#(#attrs)*
 		#vis #sig {
 			#[cfg(not(feature = "std"))]
-			::wasm_bencher::bench::before_block();
-			let result = #block;
-			#[cfg(not(feature = "std"))]
-			::wasm_bencher::bench::after_block();
-			result
+			let _block_guard = ::wasm_bencher::BlockGuard::enter();
+			#block
 		}
 	})
-	.into()
+}
+
+/// Add `#[benchmarkable]` to every method of the impl block. Methods which are
+/// already benchmarkable, also through `#[weight_meter::weight]` or
+/// `#[weight_meter::start]`, are left as they are and methods with `#[skip]` are
+/// not benchmarkable.
+fn expand_impl(mut item: ItemImpl) -> TokenStream2 {
+	for method in item.items.iter_mut() {
+		let ImplItem::Fn(method) = method else {
+			continue;
+		};
+		let attrs_len = method.attrs.len();
+		method.attrs.retain(|attr| !is_attr(attr, "wasm_bencher", "skip"));
+		if method.attrs.len() != attrs_len
+			|| method.attrs.iter().any(|attr| {
+				is_attr(attr, "wasm_bencher", "benchmarkable")
+					|| is_attr(attr, "weight_meter", "weight")
+					|| is_attr(attr, "weight_meter", "start")
+			}) {
+			continue;
+		}
+		method.attrs.push(parse_quote! { #[::wasm_bencher::benchmarkable] });
+	}
+	quote::quote! { #item }
+}
+
+/// Whether `attr` is `#[name]` or `#[krate::name]`
+fn is_attr(attr: &Attribute, krate: &str, name: &str) -> bool {
+	let path: Vec<_> = attr.path().segments.iter().map(|segment| &segment.ident).collect();
+	match path.as_slice() {
+		[ident] => *ident == name,
+		[prefix, ident] => *prefix == krate && *ident == name,
+		_ => false,
+	}
 }
@@ -0,0 +1,4 @@
+#[wasm_bencher::benchmarkable(0)]
+fn foo() {}
+
+fn main() {}
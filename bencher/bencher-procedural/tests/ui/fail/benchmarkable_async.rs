@@ -0,0 +1,4 @@
+#[wasm_bencher::benchmarkable]
+async fn foo() {}
+
+fn main() {}
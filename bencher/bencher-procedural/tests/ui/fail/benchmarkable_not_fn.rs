@@ -0,0 +1,4 @@
+#[wasm_bencher::benchmarkable]
+struct Foo;
+
+fn main() {}
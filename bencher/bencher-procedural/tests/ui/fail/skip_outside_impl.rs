@@ -0,0 +1,4 @@
+#[wasm_bencher::skip]
+fn foo() {}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[wasm_bencher::benchmarkable]
+fn add(a: u32, b: u32) -> u32 {
+	a + b
+}
+
+#[wasm_bencher::benchmarkable]
+fn checked_add(a: u32, b: u32) -> Result<u32, ()> {
+	let sum = a.checked_add(b).ok_or(())?;
+	Ok(sum)
+}
+
+fn main() {
+	assert_eq!(add(1, 2), 3);
+	assert_eq!(checked_add(u32::MAX, 1), Err(()));
+}
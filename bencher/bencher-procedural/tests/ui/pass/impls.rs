@@ -0,0 +1,34 @@
+struct Counter(u32);
+
+impl Counter {
+	#[wasm_bencher::benchmarkable]
+	fn add(&mut self, n: u32) {
+		self.0 += n;
+	}
+}
+
+#[wasm_bencher::benchmarkable]
+impl Counter {
+	fn get(&self) -> u32 {
+		self.0
+	}
+
+	#[wasm_bencher::benchmarkable]
+	fn add_one(&mut self) {
+		self.0 += 1;
+	}
+
+	#[wasm_bencher::skip]
+	fn reset(&mut self) {
+		self.0 = 0;
+	}
+}
+
+fn main() {
+	let mut counter = Counter(5);
+	counter.add(2);
+	counter.add_one();
+	assert_eq!(counter.get(), 8);
+	counter.reset();
+	assert_eq!(counter.get(), 0);
+}
@@ -7,6 +7,10 @@ use sp_storage::{ChildInfo, StateVersion, TrackedStorageKey};
 
 use super::tracker::BenchTracker;
 
+fn value_len(value: &Option<Vec<u8>>) -> u32 {
+	value.as_ref().map_or(0, |v| v.len() as u32)
+}
+
 pub struct BenchExt<'a, H, B>
 where
 	H: Hasher,
@@ -37,32 +41,34 @@ where
 	}
 
 	fn storage(&mut self, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_storage(key.to_vec());
-		self.ext.storage(key)
+		let value = self.ext.storage(key);
+		self.tracker.on_read_storage(key.to_vec(), value_len(&value));
+		value
 	}
 
 	fn storage_hash(&mut self, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_storage(key.to_vec());
+		self.tracker.on_read_storage(key.to_vec(), 0);
 		self.ext.storage_hash(key)
 	}
 
 	fn child_storage_hash(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_child_storage(child_info, key.to_vec());
+		self.tracker.on_read_child_storage(child_info, key.to_vec(), 0);
 		self.ext.child_storage_hash(child_info, key)
 	}
 
 	fn child_storage(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_child_storage(child_info, key.to_vec());
-		self.ext.child_storage(child_info, key)
+		let value = self.ext.child_storage(child_info, key);
+		self.tracker.on_read_child_storage(child_info, key.to_vec(), value_len(&value));
+		value
 	}
 
 	fn next_storage_key(&mut self, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_storage(key.to_vec());
+		self.tracker.on_read_storage(key.to_vec(), 0);
 		self.ext.next_storage_key(key)
 	}
 
 	fn next_child_storage_key(&mut self, child_info: &ChildInfo, key: &[u8]) -> Option<Vec<u8>> {
-		self.tracker.on_read_child_storage(child_info, key.to_vec());
+		self.tracker.on_read_child_storage(child_info, key.to_vec(), 0);
 		self.ext.next_child_storage_key(child_info, key)
 	}
 
@@ -72,8 +78,9 @@ where
 		limit: Option<u32>,
 		maybe_cursor: Option<&[u8]>,
 	) -> MultiRemovalResults {
-		self.tracker.on_kill_child_storage(child_info, limit);
-		self.ext.kill_child_storage(child_info, limit, maybe_cursor)
+		let removal = self.ext.kill_child_storage(child_info, limit, maybe_cursor);
+		self.tracker.on_kill_child_storage(child_info, &removal);
+		removal
 	}
 
 	fn clear_prefix(
@@ -82,8 +89,9 @@ where
 		limit: Option<u32>,
 		maybe_cursor: Option<&[u8]>,
 	) -> MultiRemovalResults {
-		self.tracker.on_clear_prefix(prefix, limit);
-		self.ext.clear_prefix(prefix, limit, maybe_cursor)
+		let removal = self.ext.clear_prefix(prefix, limit, maybe_cursor);
+		self.tracker.on_clear_prefix(prefix, &removal);
+		removal
 	}
 
 	fn clear_child_prefix(
@@ -93,12 +101,13 @@ where
 		limit: Option<u32>,
 		maybe_cursor: Option<&[u8]>,
 	) -> MultiRemovalResults {
-		self.tracker.on_clear_child_prefix(child_info, prefix, limit);
-		self.ext.clear_child_prefix(child_info, prefix, limit, maybe_cursor)
+		let removal = self.ext.clear_child_prefix(child_info, prefix, limit, maybe_cursor);
+		self.tracker.on_clear_child_prefix(child_info, prefix, &removal);
+		removal
 	}
 
 	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
-		self.tracker.on_update_storage(key.clone());
+		self.tracker.on_update_storage(key.clone(), value_len(&value));
 		self.ext.place_storage(key, value);
 	}
 
@@ -108,7 +117,7 @@ where
 		key: Vec<u8>,
 		value: Option<Vec<u8>>,
 	) {
-		self.tracker.on_update_child_storage(child_info, key.clone());
+		self.tracker.on_update_child_storage(child_info, key.clone(), value_len(&value));
 		self.ext.place_child_storage(child_info, key, value);
 	}
 
@@ -125,8 +134,9 @@ where
 	}
 
 	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>) {
-		self.tracker.on_read_storage(key.clone());
-		self.tracker.on_update_storage(key.clone());
+		// only the appended item is counted, the existing value is not read here
+		self.tracker.on_read_storage(key.clone(), 0);
+		self.tracker.on_append_storage(key.clone(), value.len() as u32);
 		self.ext.storage_append(key, value);
 	}
 
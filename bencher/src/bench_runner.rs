@@ -1,52 +1,348 @@
 use super::{
 	bench_ext::BenchExt,
+	handler::ParseError,
 	tracker::{BenchTracker, BenchTrackerExt},
 };
 use frame_support::sp_runtime::traits::HashingFor;
-use sc_executor::WasmExecutor;
-use sc_executor_common::runtime_blob::RuntimeBlob;
+use sc_executor::{
+	HeapAllocStrategy, WasmExecutionMethod, WasmExecutor, WasmtimeInstantiationStrategy,
+	DEFAULT_HEAP_ALLOC_STRATEGY,
+};
+use sc_executor_common::error::Error;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	storage::{Storage, TrackedStorageKey},
+	traits::{CallContext, CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
 use sp_externalities::Extensions;
-use sp_state_machine::{Ext, OverlayedChanges};
-use sp_std::sync::Arc;
+use sp_state_machine::{Backend, Ext, OverlayedChanges};
+use sp_std::{
+	cell::{Cell, OnceCell},
+	sync::Arc,
+};
+use sp_trie::recorder::Recorder;
+use strum::{Display, EnumString};
+
+pub use super::tracker::{AccessType, TraceEntry, TraceOp};
 
 type Header =
 	frame_support::sp_runtime::generic::Header<u32, frame_support::sp_runtime::traits::BlakeTwo256>;
 type Block =
 	frame_support::sp_runtime::generic::Block<Header, frame_support::sp_runtime::OpaqueExtrinsic>;
+type BenchmarkingState = sc_client_db::BenchmarkingState<HashingFor<Block>>;
+
+sp_externalities::decl_extension! {
+	/// Proof recorder of the benchmarking state, reset once the caches are warm.
+	pub struct ProofRecorderExt(Recorder<HashingFor<Block>>);
+}
 
 type ComposeHostFunctions = (
 	crate::sp_io::SubstrateHostFunctions,
 	super::bench::HostFunctions,
 );
 
-fn executor() -> WasmExecutor<ComposeHostFunctions> {
-	WasmExecutor::<ComposeHostFunctions>::builder()
-		.with_max_runtime_instances(1)
-		.with_runtime_cache_size(0)
-		.build()
+/// Kind of bench failure
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum FailureKind {
+	/// The bench panicked inside the runtime.
+	Panic,
+	/// The runtime trapped without panicking, e.g. out of memory.
+	Trap,
+	/// The runtime could not be executed.
+	Executor,
+	/// The bench ran but its results could not be analysed.
+	Analysis,
 }
 
-/// Run benches
-pub fn run(
-	wasm_code: &[u8],
-	method: &str,
-	call_data: &[u8],
-) -> Result<Vec<u8>, sc_executor_common::error::Error> {
-	let mut overlay = OverlayedChanges::default();
+/// Details of a failed bench
+#[derive(thiserror::Error, Serialize, Deserialize, Debug, Clone)]
+#[error("{kind}: {message}")]
+pub struct BenchFailure {
+	pub method: String,
+	pub kind: FailureKind,
+	/// Panic message of the runtime or the executor error.
+	pub message: String,
+	/// Run of the bench function that failed, counting warmup runs.
+	pub iteration: Option<u32>,
+}
 
-	let state =
-		sc_client_db::BenchmarkingState::<HashingFor<Block>>::new(Default::default(), None, true, true)?;
+impl BenchFailure {
+	fn new(method: &str, error: Error, tracker: &BenchTracker) -> Self {
+		let panic = tracker.panic_info();
+		let iteration = panic.as_ref().map_or(tracker.runs(), |(_, run)| *run);
+		let (kind, message) = match (panic, error) {
+			(Some((message, _)), _) => (FailureKind::Panic, message),
+			(None, Error::AbortedDueToTrap(trap)) => (FailureKind::Trap, trap.message),
+			(None, error) => (FailureKind::Executor, error.to_string()),
+		};
 
-	let tracker = Arc::new(BenchTracker::new());
-	let tracker_ext = BenchTrackerExt(Arc::clone(&tracker));
+		BenchFailure {
+			method: method.to_string(),
+			kind,
+			message,
+			iteration: (iteration > 0).then_some(iteration),
+		}
+	}
 
-	let mut extensions = Extensions::default();
-	extensions.register(tracker_ext);
+	/// Failure to turn the results of `method` into weights
+	pub fn analysis(method: &str, error: ParseError) -> Self {
+		BenchFailure {
+			method: method.to_string(),
+			kind: FailureKind::Analysis,
+			message: error.to_string(),
+			iteration: None,
+		}
+	}
+}
 
-	let ext = Ext::<_, _>::new(&mut overlay, &state, Some(&mut extensions));
-	let mut bench_ext = BenchExt::new(ext, tracker);
+/// Wasmtime instantiation strategy, see `sc_executor::WasmtimeInstantiationStrategy`.
+/// The legacy instance reuse strategy is no longer supported by wasmtime.
+#[derive(
+	Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum InstantiationStrategy {
+	#[default]
+	PoolingCopyOnWrite,
+	RecreateInstanceCopyOnWrite,
+	Pooling,
+	RecreateInstance,
+}
 
-	let blob = RuntimeBlob::uncompress_if_needed(wasm_code)?;
+impl From<InstantiationStrategy> for WasmtimeInstantiationStrategy {
+	fn from(strategy: InstantiationStrategy) -> Self {
+		match strategy {
+			InstantiationStrategy::PoolingCopyOnWrite => Self::PoolingCopyOnWrite,
+			InstantiationStrategy::RecreateInstanceCopyOnWrite => Self::RecreateInstanceCopyOnWrite,
+			InstantiationStrategy::Pooling => Self::Pooling,
+			InstantiationStrategy::RecreateInstance => Self::RecreateInstance,
+		}
+	}
+}
 
-	executor().uncached_call(blob, &mut bench_ext, false, method, call_data)
+/// Heap allocation strategy, see `sc_executor::HeapAllocStrategy`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeapStrategy {
+	/// Initial heap pages of the runtime plus `extra_pages`.
+	Static { extra_pages: u32 },
+	/// Grow the heap on demand up to `maximum_pages`, or 4GB when `None`.
+	Dynamic { maximum_pages: Option<u32> },
+}
+
+impl Default for HeapStrategy {
+	fn default() -> Self {
+		DEFAULT_HEAP_ALLOC_STRATEGY.into()
+	}
+}
+
+impl From<HeapAllocStrategy> for HeapStrategy {
+	fn from(strategy: HeapAllocStrategy) -> Self {
+		match strategy {
+			HeapAllocStrategy::Static { extra_pages } => Self::Static { extra_pages },
+			HeapAllocStrategy::Dynamic { maximum_pages } => Self::Dynamic { maximum_pages },
+		}
+	}
+}
+
+impl From<HeapStrategy> for HeapAllocStrategy {
+	fn from(strategy: HeapStrategy) -> Self {
+		match strategy {
+			HeapStrategy::Static { extra_pages } => Self::Static { extra_pages },
+			HeapStrategy::Dynamic { maximum_pages } => Self::Dynamic { maximum_pages },
+		}
+	}
+}
+
+/// Settings of the wasm executor running the benches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutorConfig {
+	pub instantiation_strategy: InstantiationStrategy,
+	pub heap_strategy: HeapStrategy,
+	/// Number of instances kept around by the executor.
+	pub max_runtime_instances: usize,
+}
+
+impl Default for ExecutorConfig {
+	fn default() -> Self {
+		ExecutorConfig {
+			instantiation_strategy: Default::default(),
+			heap_strategy: Default::default(),
+			max_runtime_instances: 1,
+		}
+	}
+}
+
+/// Runs benches of a wasm runtime compiled once. Each bench gets a fresh
+/// instance and storage reset to genesis, so benches can't affect each other.
+pub struct BenchRunner {
+	executor: WasmExecutor<ComposeHostFunctions>,
+	code: Vec<u8>,
+	hash: Vec<u8>,
+	/// Genesis of `state`, taken once the state is built.
+	genesis: Cell<Storage>,
+	/// State shared by all benches, reset to genesis before each of them.
+	state: OnceCell<BenchmarkingState>,
+	known_prefixes: Vec<Vec<u8>>,
+	whitelist: Vec<TrackedStorageKey>,
+	whitelisted_prefixes: Vec<Vec<u8>>,
+}
+
+impl BenchRunner {
+	/// Create a runner for `wasm_code` with the default executor settings.
+	/// The runtime is compiled on the first run and reused by the following
+	/// ones.
+	pub fn new(wasm_code: &[u8]) -> Self {
+		Self::with_config(wasm_code, ExecutorConfig::default())
+	}
+
+	/// Create a runner for `wasm_code` with the given executor settings.
+	pub fn with_config(wasm_code: &[u8], config: ExecutorConfig) -> Self {
+		let executor = WasmExecutor::<ComposeHostFunctions>::builder()
+			.with_execution_method(WasmExecutionMethod::Compiled {
+				instantiation_strategy: config.instantiation_strategy.into(),
+			})
+			.with_onchain_heap_alloc_strategy(config.heap_strategy.into())
+			.with_max_runtime_instances(config.max_runtime_instances)
+			.with_runtime_cache_size(1)
+			.build();
+
+		BenchRunner {
+			executor,
+			code: wasm_code.to_vec(),
+			hash: sp_core::blake2_256(wasm_code).to_vec(),
+			genesis: Default::default(),
+			state: Default::default(),
+			known_prefixes: Default::default(),
+			whitelist: Default::default(),
+			whitelisted_prefixes: Default::default(),
+		}
+	}
+
+	/// Start each bench from `genesis` instead of empty storage.
+	pub fn with_genesis(mut self, genesis: Storage) -> Self {
+		self.genesis = Cell::new(genesis);
+		self.state = Default::default();
+		self
+	}
+
+	/// Attribute accessed keys to the longest of these storage prefixes.
+	pub fn with_known_prefixes(mut self, known_prefixes: Vec<Vec<u8>>) -> Self {
+		self.known_prefixes = known_prefixes;
+		self
+	}
+
+	/// Whitelist reads and writes of `keys` in every bench, in addition to the
+	/// keys whitelisted by the benches themselves.
+	pub fn with_whitelist(mut self, keys: Vec<TrackedStorageKey>) -> Self {
+		self.whitelist = keys;
+		self
+	}
+
+	/// Whitelist reads and writes of all keys under `prefixes` in every bench,
+	/// e.g. `Map::<T>::final_prefix()` to whitelist every entry of a storage map.
+	pub fn with_whitelisted_prefixes(mut self, prefixes: Vec<Vec<u8>>) -> Self {
+		self.whitelisted_prefixes = prefixes;
+		self
+	}
+
+	/// The state at genesis, built on the first call
+	fn state(&self) -> Result<&BenchmarkingState, Error> {
+		if let Some(state) = self.state.get() {
+			state.wipe()?;
+			return Ok(state);
+		}
+		// keep the genesis until the state is built, a failed build can be retried
+		let genesis = self.genesis.take();
+		let state = BenchmarkingState::new(genesis.clone(), None, true, true)
+			.inspect_err(|_| self.genesis.set(genesis))?;
+		Ok(self.state.get_or_init(|| state))
+	}
+
+	fn tracker(&self) -> BenchTracker {
+		let tracker = BenchTracker::new(self.known_prefixes.clone());
+		for key in &self.whitelist {
+			tracker.whitelist(key.key.clone(), true, true);
+		}
+		for prefix in &self.whitelisted_prefixes {
+			tracker.whitelist_prefix(prefix.clone(), true, true);
+		}
+		tracker
+	}
+
+	/// Run bench `method` in a new instance
+	pub fn run(&self, method: &str, call_data: &[u8]) -> Result<Vec<u8>, BenchFailure> {
+		let tracker = Arc::new(self.tracker());
+
+		self.execute(method, call_data, Arc::clone(&tracker))
+			.map_err(|error| BenchFailure::new(method, error, &tracker))
+	}
+
+	/// Run bench `method` like `run`, also returning every storage access of
+	/// `run`, counting warmup runs from 1. Recording the trace slows down
+	/// that run.
+	pub fn trace(
+		&self,
+		method: &str,
+		call_data: &[u8],
+		run: u32,
+	) -> Result<(Vec<u8>, Vec<TraceEntry>), BenchFailure> {
+		let tracker = Arc::new(self.tracker().with_trace(run));
+
+		self.execute(method, call_data, Arc::clone(&tracker))
+			.map(|output| (output, tracker.trace()))
+			.map_err(|error| BenchFailure::new(method, error, &tracker))
+	}
+
+	fn execute(
+		&self,
+		method: &str,
+		call_data: &[u8],
+		tracker: Arc<BenchTracker>,
+	) -> Result<Vec<u8>, Error> {
+		let mut overlay = OverlayedChanges::default();
+
+		let state = self.state()?;
+
+		let tracker_ext = BenchTrackerExt(Arc::clone(&tracker));
+
+		let mut extensions = Extensions::default();
+		extensions.register(tracker_ext);
+		if let Some(recorder) = state.recorder() {
+			extensions.register(ProofRecorderExt(recorder));
+		}
+
+		let ext = Ext::<_, _>::new(&mut overlay, state, Some(&mut extensions));
+		let mut bench_ext = BenchExt::new(ext, tracker);
+
+		let code_fetcher = WrappedRuntimeCode(self.code.as_slice().into());
+		let runtime_code = RuntimeCode {
+			code_fetcher: &code_fetcher,
+			heap_pages: None,
+			hash: self.hash.clone(),
+		};
+
+		self.executor
+			.call(
+				&mut bench_ext,
+				&runtime_code,
+				method,
+				call_data,
+				CallContext::Onchain,
+			)
+			.0
+	}
+}
+
+/// Run a single bench with the default executor settings, starting from
+/// `genesis`. Use `BenchRunner` to run several benches without recompiling
+/// the runtime.
+pub fn run(
+	wasm_code: &[u8],
+	genesis: Storage,
+	method: &str,
+	call_data: &[u8],
+) -> Result<Vec<u8>, BenchFailure> {
+	BenchRunner::new(wasm_code).with_genesis(genesis).run(method, call_data)
 }
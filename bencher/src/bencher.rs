@@ -1,12 +1,97 @@
 use codec::{Decode, Encode};
-use sp_std::prelude::Vec;
+use sp_core::storage::TrackedStorageKey;
+use sp_std::{ops::RangeInclusive, prelude::Vec};
 
+/// Number of distinct values each component is sampled at.
+pub const COMPONENT_STEPS: u32 = 10;
+
+/// Number of measured runs when none is configured.
+pub const DEFAULT_ITERATIONS: u32 = 1_000;
+
+/// Iteration settings of a bench, passed to the wasm call as call data.
+#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BenchConfig {
+	/// Number of measured runs.
+	pub iterations: Option<u32>,
+	/// Number of runs executed and discarded before measuring.
+	pub warmup: Option<u32>,
+}
+
+impl BenchConfig {
+	/// Use settings of `other` where `self` has none.
+	pub fn or(self, other: Self) -> Self {
+		Self {
+			iterations: self.iterations.or(other.iterations),
+			warmup: self.warmup.or(other.warmup),
+		}
+	}
+
+	pub fn iterations(&self) -> u32 {
+		self.iterations.unwrap_or(DEFAULT_ITERATIONS)
+	}
+
+	pub fn warmup(&self) -> u32 {
+		self.warmup.unwrap_or_default()
+	}
+}
+
+/// State of the storage caches when the measured code starts.
+#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(
+	feature = "std",
+	derive(serde::Serialize, serde::Deserialize, strum::Display)
+)]
+#[cfg_attr(feature = "std", serde(rename_all = "kebab-case"))]
+#[cfg_attr(feature = "std", strum(serialize_all = "kebab-case"))]
+pub enum StorageMode {
+	/// Changes are committed and the state is reopened with empty caches, so
+	/// every access hits the database.
+	#[default]
+	Cold,
+	/// Keys accessed since the previous measurement are read again after the
+	/// commit, so they are served from the trie cache. Caching doesn't change
+	/// what goes into the proof, the proof size is recorded like with cold caches.
+	Warm,
+}
+
+/// Results of one named measurement of a bench function.
 #[derive(Encode, Decode, Default, Clone, PartialEq, Eq, Debug)]
-pub struct Bencher {
-	pub method: Vec<u8>,
+pub struct Measurement {
+	pub name: Vec<u8>,
+	/// Storage mode of the last recorded run.
+	pub storage_mode: StorageMode,
 	pub elapses: Vec<u128>,
+	/// Storage proof size of each entry in `elapses`.
+	pub proof_sizes: Vec<u32>,
+	/// Component values used for each entry in `elapses`.
+	pub component_values: Vec<Vec<u32>>,
+	/// Reads of each entry in `elapses`.
+	pub reads: Vec<u32>,
+	/// Writes of each entry in `elapses`.
+	pub writes: Vec<u32>,
+	/// Reads and writes per prefix of the last run.
 	pub keys: Vec<u8>,
-	pub warnings: Vec<u8>,
+	/// Accessed keys of child tries.
+	pub child_keys: Vec<u8>,
+	/// Reads and writes of each accessed key.
+	pub accessed_keys: Vec<u8>,
+	/// Bytes read and written per prefix.
+	pub bytes: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, Debug)]
+pub struct Bencher {
+	/// Name used by `bench`, the bench function name unless overridden with `name`.
+	pub method: Vec<u8>,
+	/// Declared components as `(name, low, high)`.
+	pub components: Vec<(Vec<u8>, u32, u32)>,
+	pub measurements: Vec<Measurement>,
+	#[codec(skip)]
+	storage_mode: StorageMode,
+	#[codec(skip)]
+	runs: u32,
+	#[codec(skip)]
+	current_values: Vec<u32>,
 }
 
 impl Bencher {
@@ -16,6 +101,32 @@ impl Bencher {
 			..Default::default()
 		}
 	}
+
+	/// Override the name of the measurement recorded by `bench`.
+	pub fn name(&mut self, name: &str) -> &mut Self {
+		self.method = name.as_bytes().to_vec();
+		self
+	}
+
+	/// Run the measured code with cold or warm storage caches, cold by default.
+	pub fn storage_mode(&mut self, mode: StorageMode) -> &mut Self {
+		self.storage_mode = mode;
+		self
+	}
+
+	fn measurement(&mut self, name: &[u8]) -> &mut Measurement {
+		let index = match self.measurements.iter().position(|x| x.name == name) {
+			Some(index) => index,
+			None => {
+				self.measurements.push(Measurement {
+					name: name.to_vec(),
+					..Default::default()
+				});
+				self.measurements.len() - 1
+			},
+		};
+		&mut self.measurements[index]
+	}
 }
 
 #[inline]
@@ -32,39 +143,199 @@ impl Bencher {
 		crate::bench::whitelist(key, read, write);
 	}
 
-	pub fn before_run(&self) {
+	/// Whitelist all keys under `prefix`, e.g. `Map::<T>::final_prefix()` to
+	/// whitelist every entry of a storage map.
+	pub fn whitelist_prefix(&mut self, prefix: Vec<u8>, read: bool, write: bool) {
+		#[cfg(not(feature = "std"))]
+		crate::bench::whitelist_prefix(prefix, read, write);
+	}
+
+	/// Whitelist reads and writes of `keys`, e.g. the keys declared with
+	/// `#[pallet::whitelist_storage]` returned by
+	/// `AllPalletsWithSystem::whitelisted_storage_keys()`.
+	pub fn whitelist_keys(&mut self, keys: Vec<TrackedStorageKey>) {
+		for key in keys {
+			self.whitelist(key.key, true, true);
+		}
+	}
+
+	/// Declare a component and get its value for the current run.
+	///
+	/// Components are varied one at a time over `COMPONENT_STEPS` evenly
+	/// spaced values in `range` while the others are kept at their maximum,
+	/// so the parser can fit a base weight plus a slope per component.
+	pub fn component(&mut self, name: &str, range: RangeInclusive<u32>) -> u32 {
+		let (low, high) = (*range.start(), *range.end());
+		assert!(low <= high, "invalid range for component `{name}`");
+
+		let index = self.current_values.len();
+		match self.components.get(index) {
+			Some((declared, ..)) => {
+				assert!(
+					declared == name.as_bytes(),
+					"components must be declared in the same order"
+				)
+			},
+			None => self.components.push((name.as_bytes().to_vec(), low, high)),
+		}
+
+		let run = self.runs.saturating_sub(1);
+		// before the first run completes only the first component is known
+		let count = core::cmp::max(self.components.len() as u32, 1);
+		let value = if (run / COMPONENT_STEPS) % count == index as u32 {
+			let step = run % COMPONENT_STEPS;
+			low + ((high - low) as u64 * step as u64 / (COMPONENT_STEPS - 1) as u64) as u32
+		} else {
+			high
+		};
+
+		self.current_values.push(value);
+		value
+	}
+
+	/// Discard everything measured so far, used after warmup runs.
+	pub fn reset(&mut self) {
+		self.runs = 0;
+		self.measurements.clear();
+	}
+
+	pub fn before_run(&mut self) {
+		self.runs += 1;
+		self.current_values.clear();
+
 		#[cfg(not(feature = "std"))]
 		{
+			crate::bench::start_run();
 			crate::bench::commit_db();
 			crate::bench::wipe_db();
 		}
 	}
 
-	pub fn bench<T, F>(&mut self, mut inner: F) -> T
+	pub fn bench<T, F>(&mut self, inner: F) -> T
+	where
+		F: FnMut() -> T,
+	{
+		let name = self.method.clone();
+		self.measure(&name, inner)
+	}
+
+	/// Record a measurement under its own name, reported as a separate bench.
+	/// Useful to benchmark sibling code paths sharing the same setup.
+	pub fn bench_named<T, F>(&mut self, name: &str, inner: F) -> T
+	where
+		F: FnMut() -> T,
+	{
+		self.measure(name.as_bytes(), inner)
+	}
+
+	fn measure<T, F>(&mut self, name: &[u8], mut inner: F) -> T
 	where
 		F: FnMut() -> T,
 	{
 		#[cfg(not(feature = "std"))]
 		{
 			crate::bench::commit_db();
+			if self.storage_mode == StorageMode::Warm {
+				crate::bench::warm_storage();
+			}
 			crate::bench::reset_read_write_count();
 			crate::bench::start_timer();
 		}
 
 		let ret = black_box(inner());
 
+		#[cfg(not(feature = "std"))]
+		let elapsed = crate::bench::end_timer().saturating_sub(crate::bench::redundant_time());
+
+		let component_values = self.current_values.clone();
+		let storage_mode = self.storage_mode;
+		let measurement = self.measurement(name);
+		measurement.storage_mode = storage_mode;
+
 		#[cfg(not(feature = "std"))]
 		{
-			let elapsed = crate::bench::end_timer().saturating_sub(crate::bench::redundant_time());
-			self.elapses.push(elapsed);
+			measurement.elapses.push(elapsed);
+
+			// proof recorded since the last commit, or since the caches were warmed
+			measurement.proof_sizes.push(crate::bench::proof_size());
 
 			crate::bench::commit_db();
 
+			let (reads, writes) = <(u32, u32)>::decode(&mut &crate::bench::reads_and_writes()[..])
+				.unwrap_or_default();
+			measurement.reads.push(reads);
+			measurement.writes.push(writes);
+
 			// changed keys
-			self.keys = crate::bench::read_written_keys();
-			self.warnings = crate::bench::warnings();
+			measurement.keys = crate::bench::read_written_keys();
+			measurement.child_keys = crate::bench::read_written_child_keys();
+			measurement.accessed_keys = crate::bench::accessed_keys();
+			measurement.bytes = crate::bench::read_written_bytes();
 		}
 
+		measurement.component_values.push(component_values);
+
 		ret
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Component values of `runs` runs declaring `n` then `m`
+	fn values(runs: u32) -> Vec<(u32, u32)> {
+		let mut b = Bencher::default();
+		(0..runs)
+			.map(|_| {
+				b.before_run();
+				(b.component("n", 0..=9), b.component("m", 10..=100))
+			})
+			.collect()
+	}
+
+	#[test]
+	fn components_are_varied_one_at_a_time() {
+		let values = values(4 * COMPONENT_STEPS);
+		let steps = COMPONENT_STEPS as usize;
+		for block in values.chunks(steps).step_by(2) {
+			assert_eq!(block, (0..=9).map(|n| (n, 100)).collect::<Vec<_>>());
+		}
+		for block in values.chunks(steps).skip(1).step_by(2) {
+			assert_eq!(block, (0..=9).map(|m| (9, 10 + 10 * m)).collect::<Vec<_>>());
+		}
+	}
+
+	#[test]
+	fn fixed_component_keeps_its_value() {
+		let mut b = Bencher::default();
+		for _ in 0..COMPONENT_STEPS {
+			b.before_run();
+			assert_eq!(b.component("k", 4..=4), 4);
+		}
+	}
+
+	#[test]
+	fn reset_restarts_stepping() {
+		let mut b = Bencher::default();
+		for _ in 0..3 {
+			b.before_run();
+			b.component("n", 0..=9);
+		}
+		b.reset();
+		b.before_run();
+		assert_eq!(b.component("n", 0..=9), 0);
+		b.before_run();
+		assert_eq!(b.component("n", 0..=9), 1);
+	}
+
+	#[test]
+	#[should_panic(expected = "components must be declared in the same order")]
+	fn components_keep_their_order() {
+		let mut b = Bencher::default();
+		b.before_run();
+		b.component("n", 0..=9);
+		b.current_values.clear();
+		b.component("m", 0..=9);
+	}
+}
@@ -0,0 +1,326 @@
+use crate::{
+	bench_runner::{ExecutorConfig, HeapStrategy},
+	handler::{Estimator, TraceFormat},
+	BenchConfig,
+};
+use regex::Regex;
+
+/// Command line options of the `main!` bench runner.
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+	/// Iteration settings overriding the ones declared in `benches!`.
+	pub config: BenchConfig,
+	/// Estimator used for the generated weights.
+	pub estimator: Estimator,
+	/// Save output to json.
+	pub json: bool,
+	/// Only run benches matching one of these patterns.
+	pub filters: Vec<String>,
+	/// Skip benches matching one of these patterns.
+	pub skip: Vec<String>,
+	/// Match `filters` and `skip` against the full bench name instead of as regular expressions.
+	pub exact: bool,
+	/// Print the available benches instead of running them.
+	pub list: bool,
+	/// Run each bench once as a smoke test, without computing results.
+	pub dry_run: bool,
+	/// Print reads and writes of each accessed key.
+	pub verbose: bool,
+	/// Settings of the wasm executor.
+	pub executor: ExecutorConfig,
+	/// Raw chain spec to load the genesis storage from.
+	pub genesis: Option<String>,
+	/// State snapshot loaded on top of the genesis storage.
+	pub snapshot: Option<String>,
+	/// Run of each bench to trace storage accesses of, counting warmup runs from 1.
+	pub trace: Option<u32>,
+	/// File format of the storage access trace.
+	pub trace_format: TraceFormat,
+}
+
+/// Invalid command line arguments
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+	#[error("`{0}` expects a value")]
+	MissingValue(String),
+	#[error("`{flag}` expects a number, got `{value}`")]
+	InvalidNumber { flag: String, value: String },
+	#[error("`{flag}` expects one of {expected}, got `{value}`")]
+	InvalidValue {
+		flag: String,
+		value: String,
+		expected: &'static str,
+	},
+	#[error("`--iterations` must be at least 2 to fit the results, got {0}, use `--dry-run` for a single run")]
+	TooFewIterations(u32),
+	#[error("invalid filter `{pattern}`: {error}")]
+	InvalidFilter { pattern: String, error: String },
+}
+
+/// Flags of the cargo bench and test harnesses which don't take a value.
+const FLAGS_WITHOUT_VALUE: &[&str] = &[
+	"--bench",
+	"--test",
+	"--nocapture",
+	"--show-output",
+	"--quiet",
+	"--ignored",
+	"--include-ignored",
+];
+
+impl Options {
+	/// Parse options from the process arguments. Unknown flags and their values,
+	/// like the `--bench` flag passed by cargo, are ignored.
+	pub fn from_args() -> Result<Self, Error> {
+		Self::parse(std::env::args().skip(1))
+	}
+
+	pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+		let mut options = Options::default();
+		let mut args = args.into_iter().peekable();
+
+		while let Some(arg) = args.next() {
+			let (flag, inline_value) = match arg.split_once('=') {
+				Some((flag, value)) if flag.starts_with("--") => {
+					(flag.to_string(), Some(value.to_string()))
+				},
+				_ => (arg, None),
+			};
+			let mut value = |name: &str| -> Result<String, Error> {
+				inline_value
+					.clone()
+					.or_else(|| args.next())
+					.ok_or_else(|| Error::MissingValue(name.to_string()))
+			};
+			let number = |name: &str, value: String| -> Result<u32, Error> {
+				value.parse().map_err(|_| Error::InvalidNumber {
+					flag: name.to_string(),
+					value,
+				})
+			};
+			let choice = |name: &str, value: String, expected: &'static str| Error::InvalidValue {
+				flag: name.to_string(),
+				value,
+				expected,
+			};
+
+			match flag.as_str() {
+				"json" | "--json" => options.json = true,
+				"--iterations" => options.config.iterations = Some(number(&flag, value(&flag)?)?),
+				"--warmup" => options.config.warmup = Some(number(&flag, value(&flag)?)?),
+				"--skip" => options.skip.push(value(&flag)?),
+				"--exact" => options.exact = true,
+				"--list" => options.list = true,
+				"--dry-run" => options.dry_run = true,
+				"-v" | "--verbose" => options.verbose = true,
+				"--genesis" => options.genesis = Some(value(&flag)?),
+				"--snapshot" => options.snapshot = Some(value(&flag)?),
+				"--trace" => options.trace = Some(number(&flag, value(&flag)?)?),
+				"--trace-format" => {
+					let value = value(&flag)?;
+					options.trace_format =
+						value.parse().map_err(|_| choice(&flag, value, "json, csv"))?;
+				},
+				"--estimator" => {
+					let value = value(&flag)?;
+					options.estimator = value.parse().map_err(|_| {
+						choice(&flag, value, "regression, mean, median, filtered-mean, min")
+					})?;
+				},
+				"--instantiation-strategy" => {
+					let value = value(&flag)?;
+					options.executor.instantiation_strategy = value.parse().map_err(|_| {
+						choice(
+							&flag,
+							value,
+							"pooling-copy-on-write, recreate-instance-copy-on-write, pooling, recreate-instance",
+						)
+					})?;
+				},
+				"--heap-pages" => {
+					options.executor.heap_strategy = HeapStrategy::Static {
+						extra_pages: number(&flag, value(&flag)?)?,
+					};
+				},
+				"--max-heap-pages" => {
+					options.executor.heap_strategy = HeapStrategy::Dynamic {
+						maximum_pages: Some(number(&flag, value(&flag)?)?),
+					};
+				},
+				"--max-runtime-instances" => {
+					options.executor.max_runtime_instances = number(&flag, value(&flag)?)? as usize;
+				},
+				filter if !filter.starts_with('-') => options.filters.push(filter.to_string()),
+				// the value of an unknown flag is not a filter
+				unknown => {
+					if unknown.starts_with("--")
+						&& inline_value.is_none()
+						&& !FLAGS_WITHOUT_VALUE.contains(&unknown)
+					{
+						args.next_if(|next| !next.starts_with('-'));
+					}
+				},
+			}
+		}
+
+		if options.dry_run {
+			options.config = BenchConfig {
+				iterations: Some(1),
+				warmup: Some(0),
+			};
+		} else if let Some(iterations @ 0..=1) = options.config.iterations {
+			return Err(Error::TooFewIterations(iterations));
+		}
+
+		if !options.exact {
+			for pattern in options.filters.iter().chain(&options.skip) {
+				Regex::new(pattern).map_err(|error| Error::InvalidFilter {
+					pattern: pattern.clone(),
+					error: error.to_string(),
+				})?;
+			}
+		}
+
+		Ok(options)
+	}
+
+	/// Whether the bench `name` is selected by the filters.
+	pub fn is_selected(&self, name: &str) -> bool {
+		let matches = |pattern: &String| {
+			if self.exact {
+				name == pattern
+			} else {
+				// patterns are validated by `parse`
+				Regex::new(pattern).is_ok_and(|regex| regex.is_match(name))
+			}
+		};
+
+		(self.filters.is_empty() || self.filters.iter().any(matches))
+			&& !self.skip.iter().any(matches)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(args: &[&str]) -> Result<Options, Error> {
+		Options::parse(args.iter().map(|arg| arg.to_string()))
+	}
+
+	#[test]
+	fn parses_flags() {
+		let options = parse(&[
+			"--json",
+			"-v",
+			"--list",
+			"--iterations",
+			"50",
+			"--warmup=5",
+			"--bench",
+		])
+		.unwrap();
+		assert!(options.json);
+		assert!(options.verbose);
+		assert!(options.list);
+		assert_eq!(options.config.iterations, Some(50));
+		assert_eq!(options.config.warmup, Some(5));
+		assert!(options.filters.is_empty());
+	}
+
+	#[test]
+	fn parses_inline_values() {
+		let options = parse(&[
+			"--estimator=median",
+			"--trace-format=csv",
+			"--trace=3",
+			"--heap-pages=64",
+		])
+		.unwrap();
+		assert_eq!(options.estimator, Estimator::Median);
+		assert_eq!(options.trace_format, TraceFormat::Csv);
+		assert_eq!(options.trace, Some(3));
+		assert_eq!(
+			options.executor.heap_strategy,
+			HeapStrategy::Static { extra_pages: 64 }
+		);
+	}
+
+	#[test]
+	fn parses_filters() {
+		let options = parse(&["set_", "--skip", "warm", "--skip=whitelist", "read"]).unwrap();
+		assert_eq!(options.filters, vec!["set_", "read"]);
+		assert_eq!(options.skip, vec!["warm", "whitelist"]);
+		assert!(options.is_selected("set_foo"));
+		assert!(options.is_selected("read_genesis_foo"));
+		assert!(!options.is_selected("set_value_warm"));
+		assert!(!options.is_selected("set_foo_with_whitelist"));
+		assert!(!options.is_selected("clear_prefix"));
+	}
+
+	#[test]
+	fn exact_matches_full_name() {
+		let options = parse(&["--exact", "set_foo", "--skip", "set_value"]).unwrap();
+		assert!(options.is_selected("set_foo"));
+		assert!(!options.is_selected("set_foo_after_error"));
+		assert!(!options.is_selected("set_value"));
+
+		// patterns aren't regular expressions with `--exact`
+		assert!(parse(&["--exact", "set_("]).is_ok());
+	}
+
+	#[test]
+	fn skips_value_of_unknown_flags() {
+		let options = parse(&[
+			"--color",
+			"always",
+			"set_foo",
+			"--format=terse",
+			"--nocapture",
+			"read",
+			"--bench",
+		])
+		.unwrap();
+		assert_eq!(options.filters, vec!["set_foo", "read"]);
+
+		let options = parse(&["--color", "--json"]).unwrap();
+		assert!(options.json);
+		assert!(options.filters.is_empty());
+	}
+
+	#[test]
+	fn dry_run_overrides_iterations() {
+		let options = parse(&["--iterations", "50", "--warmup", "5", "--dry-run"]).unwrap();
+		assert!(options.dry_run);
+		assert_eq!(options.config.iterations, Some(1));
+		assert_eq!(options.config.warmup, Some(0));
+	}
+
+	#[test]
+	fn rejects_invalid_values() {
+		assert_eq!(
+			parse(&["--iterations"]).unwrap_err(),
+			Error::MissingValue("--iterations".into())
+		);
+		assert_eq!(
+			parse(&["--warmup", "many"]).unwrap_err(),
+			Error::InvalidNumber {
+				flag: "--warmup".into(),
+				value: "many".into()
+			}
+		);
+		assert!(matches!(
+			parse(&["--estimator=best"]),
+			Err(Error::InvalidValue { value, .. }) if value == "best"
+		));
+		assert!(matches!(
+			parse(&["set_("]),
+			Err(Error::InvalidFilter { .. })
+		));
+		assert_eq!(
+			parse(&["--iterations=1"]).unwrap_err(),
+			Error::TooFewIterations(1)
+		);
+		assert!(parse(&["--iterations=1", "--dry-run"]).is_ok());
+	}
+}
@@ -1,13 +1,14 @@
 use crate::{
-	colorize::{cyan, green_bold, yellow_bold},
-	tracker::Warning,
-	Bencher,
+	bench_runner::{BenchFailure, ExecutorConfig, TraceEntry},
+	colorize::{cyan, green_bold, red_bold},
+	Bencher, Measurement, StorageMode, COMPONENT_STEPS,
 };
 use codec::Decode;
 use linregress::{FormulaRegressionBuilder, RegressionDataBuilder};
 use serde::{Deserialize, Serialize};
 use sp_core::hexdisplay::HexDisplay;
 use std::{io::Write, string::String, time::Duration};
+use strum::{Display, EnumString};
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct StorageMetadata {
@@ -23,58 +24,451 @@ pub struct StorageMetadata {
 	pub max_size: Option<u32>,
 }
 
+/// Estimator used to derive the bench time from the measured runs.
+#[derive(
+	Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Estimator {
+	/// Intercept of a linear regression over the runs.
+	#[default]
+	Regression,
+	Mean,
+	Median,
+	/// Mean of the runs left after rejecting outliers.
+	FilteredMean,
+	Min,
+}
+
+/// File format of a storage access trace.
+#[derive(
+	Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum TraceFormat {
+	#[default]
+	Json,
+	Csv,
+}
+
+/// Distribution of the measured run times.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Stats {
+	pub min: Duration,
+	pub max: Duration,
+	pub mean: Duration,
+	pub median: Duration,
+	pub stddev: Duration,
+	pub p95: Duration,
+	pub p99: Duration,
+	/// Mean of the runs left after rejecting outliers.
+	pub filtered_mean: Duration,
+	/// Number of runs rejected as outliers.
+	pub outliers: u32,
+}
+
+/// Modified z-score above which a run is considered an outlier.
+const OUTLIER_THRESHOLD: f64 = 3.5;
+
+impl Stats {
+	pub fn new(elapses: &[u128]) -> Self {
+		if elapses.is_empty() {
+			return Self::default();
+		}
+
+		let mut sorted: Vec<f64> = elapses.iter().map(|x| *x as f64).collect();
+		sorted.sort_by(f64::total_cmp);
+
+		let mean = sorted.iter().sum::<f64>() / sorted.len() as f64;
+		let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / sorted.len() as f64;
+		let median = percentile(&sorted, 50.0);
+
+		// reject outliers by median absolute deviation
+		let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - median).abs()).collect();
+		deviations.sort_by(f64::total_cmp);
+		let mad = percentile(&deviations, 50.0);
+		let kept: Vec<f64> = if mad > 0.0 {
+			sorted
+				.iter()
+				.copied()
+				.filter(|x| 0.6745 * (x - median).abs() / mad <= OUTLIER_THRESHOLD)
+				.collect()
+		} else {
+			sorted.clone()
+		};
+		let filtered_mean = kept.iter().sum::<f64>() / kept.len() as f64;
+
+		let nanos = |x: f64| Duration::from_nanos(x.max(0.0) as u64);
+
+		Stats {
+			min: nanos(sorted[0]),
+			max: nanos(sorted[sorted.len() - 1]),
+			mean: nanos(mean),
+			median: nanos(median),
+			stddev: nanos(variance.sqrt()),
+			p95: nanos(percentile(&sorted, 95.0)),
+			p99: nanos(percentile(&sorted, 99.0)),
+			filtered_mean: nanos(filtered_mean),
+			outliers: (sorted.len() - kept.len()) as u32,
+		}
+	}
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+	let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+	sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Proof size of one trie layer, 15 sibling hashes of 33 bytes each.
+const TRIE_LAYER_PROOF_SIZE: u64 = 15 * 33;
+
+/// Trie layers assumed on top of the ones needed to hold `max_values`.
+const ADDITIONAL_TRIE_LAYERS: u32 = 2;
+
+impl StorageMetadata {
+	/// Worst case proof size of reading one item of the storage, or none if
+	/// the storage has no maximum size.
+	pub fn max_proof_size(&self) -> Option<u64> {
+		let max_size = self.max_size?;
+		let max_values = self.max_values.unwrap_or(u32::MAX) as u64;
+
+		let mut layers = ADDITIONAL_TRIE_LAYERS;
+		let mut capacity = 1u64;
+		while capacity < max_values {
+			capacity *= 16;
+			layers += 1;
+		}
+
+		Some(max_size as u64 + TRIE_LAYER_PROOF_SIZE * layers as u64)
+	}
+}
+
+/// Name of the storage a key or prefix belongs to, the key itself for
+/// well-known keys like `:code`.
+pub fn key_name(key: &[u8], storage_infos: &[StorageMetadata]) -> String {
+	match storage_infos
+		.iter()
+		.filter(|x| key.starts_with(&x.prefix))
+		.max_by_key(|x| x.prefix.len())
+	{
+		Some(info) => format!("{}::{}", info.pallet_name, info.storage_name),
+		None => match std::str::from_utf8(key) {
+			Ok(name) if key.starts_with(b":") => name.to_string(),
+			_ => format!("Unknown 0x{}", HexDisplay::from(&key)),
+		},
+	}
+}
+
+/// Worst case proof size of all reads of a bench. Unknown and unbounded
+/// storages are skipped and returned separately.
+pub fn estimate_proof_size(
+	keys: &[(Vec<u8>, u32, u32)],
+	storage_infos: &[StorageMetadata],
+) -> (u64, Vec<Vec<u8>>) {
+	let mut estimate = 0u64;
+	let mut unbounded = vec![];
+
+	for (prefix, reads, _writes) in keys {
+		if *reads == 0 {
+			continue;
+		}
+		match storage_infos
+			.iter()
+			.find(|x| x.prefix.eq(prefix))
+			.and_then(|info| info.max_proof_size())
+		{
+			Some(size) => estimate = estimate.saturating_add(size.saturating_mul(*reads as u64)),
+			None => unbounded.push(prefix.clone()),
+		}
+	}
+
+	(estimate, unbounded)
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct ComponentSlope {
+	/// Name of the component.
+	pub name: String,
+	/// Lowest value the component was benchmarked with.
+	pub low: u32,
+	/// Highest value the component was benchmarked with.
+	pub high: u32,
+	/// Extra time in nanoseconds per unit of the component.
+	pub slope: f64,
+	/// Extra proof size in bytes per unit of the component.
+	pub proof_slope: f64,
+	/// Extra reads per unit of the component.
+	pub read_slope: f64,
+	/// Extra writes per unit of the component.
+	pub write_slope: f64,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct BenchData {
 	pub name: String,
 	pub time: Duration,
+	/// Storage proof size in bytes.
+	pub proof_size: u64,
+	/// Estimator used for `time`. The regression is always used when components are declared.
+	pub estimator: Estimator,
+	pub stats: Stats,
+	pub storage_mode: StorageMode,
+	pub components: Vec<ComponentSlope>,
+	/// Reads of the worst run without components, base reads otherwise.
 	pub reads: u32,
+	/// Writes of the worst run without components, base writes otherwise.
 	pub writes: u32,
+	/// Total length of the values read.
+	pub bytes_read: u64,
+	/// Total length of the values written.
+	pub bytes_written: u64,
+	/// Reads and writes per prefix of the last run.
 	pub keys: Vec<(Vec<u8>, u32, u32)>,
-	pub warnings: Vec<Warning>,
+	/// Bytes read and written per prefix, as `(prefix, bytes read, bytes written)`.
+	/// Child tries are listed by their child storage key.
+	pub key_bytes: Vec<(Vec<u8>, u32, u32)>,
+	/// Reads and writes of each accessed key, removals are listed by prefix.
+	pub accessed_keys: Vec<(Vec<u8>, u32, u32)>,
+	/// Accessed child trie keys as `(child storage key, prefix, reads, writes)`.
+	pub child_keys: Vec<(Vec<u8>, Vec<u8>, u32, u32)>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct ComponentOutput {
+	pub name: String,
+	pub weight: u64,
+	pub proof_size: u64,
+	pub reads: u32,
+	pub writes: u32,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct BenchDataOutput {
 	pub name: String,
 	pub weight: u64,
+	pub proof_size: u64,
+	/// Worst case proof size from the max size of the read storages.
+	pub estimated_proof_size: u64,
+	pub storage_mode: StorageMode,
+	pub components: Vec<ComponentOutput>,
 	pub reads: u32,
 	pub writes: u32,
+	pub bytes_read: u64,
+	pub bytes_written: u64,
 	pub comments: Vec<String>,
+	/// Executor settings the bench was run with.
+	pub executor: ExecutorConfig,
+}
+
+/// Fit `Y ~ X` against the run index when there are no components, or
+/// `Y ~ c1 + c2 + ..` against component values otherwise. Returns the base
+/// value followed by the slope of each varied component.
+fn regression(
+	y: Vec<f64>,
+	component_values: &[Vec<u32>],
+	varied: &[usize],
+) -> Result<Vec<f64>, ParseError> {
+	let mut data = vec![("Y".to_string(), y)];
+
+	if varied.is_empty() {
+		let x: Vec<f64> = (0..component_values.len()).map(|x| x as f64).collect();
+		data.push(("X".to_string(), x));
+	} else {
+		for index in varied {
+			let x = component_values.iter().map(|v| v[*index] as f64).collect();
+			data.push((format!("C{index}"), x));
+		}
+	}
+
+	let regressors: Vec<String> = data.iter().skip(1).map(|(name, _)| name.clone()).collect();
+	let formula = format!("Y ~ {}", regressors.join(" + "));
+	let data = RegressionDataBuilder::new().build_from(data)?;
+	let parameters = FormulaRegressionBuilder::new()
+		.data(&data)
+		.formula(formula)
+		.fit_without_statistics()?;
+
+	if varied.is_empty() {
+		Ok(vec![parameters[0]])
+	} else {
+		Ok(parameters)
+	}
+}
+
+/// Bench results that can't be turned into weights
+#[derive(thiserror::Error, Debug)]
+pub enum ParseError {
+	#[error("component `{name}` took a single value in {runs} runs, run at least {required} iterations to vary every component")]
+	ComponentNotVaried {
+		name: String,
+		runs: usize,
+		required: u32,
+	},
+	#[error("run {run} recorded {found} component values but {expected} components are declared, declare every component before measuring, in every run")]
+	ComponentValuesMismatch {
+		run: usize,
+		expected: usize,
+		found: usize,
+	},
+	#[error("failed to decode the bench results: {0}")]
+	Decode(#[from] codec::Error),
+	#[error("failed to fit the bench results, run at least 2 iterations: {0}")]
+	Regression(#[from] linregress::Error),
+}
+
+/// Handle bench results, one `BenchData` per measurement
+pub fn parse(output: Vec<u8>, estimator: Estimator) -> Result<Vec<BenchData>, ParseError> {
+	let bencher = <Bencher as Decode>::decode(&mut &output[..])?;
+	bencher
+		.measurements
+		.iter()
+		.map(|measurement| parse_measurement(&bencher.components, measurement, estimator))
+		.collect()
 }
 
-/// Handle bench results
-pub fn parse(output: Vec<u8>) -> BenchData {
-	let bencher = <Bencher as Decode>::decode(&mut &output[..]).unwrap();
-	let warnings = <Vec<Warning> as Decode>::decode(&mut &bencher.warnings[..]).unwrap();
-	let y: Vec<f64> = bencher.elapses.into_iter().map(|x| x as f64).collect();
-	let x: Vec<f64> = (0..y.len()).map(|x| x as f64).collect();
-	let data = vec![("Y", y), ("X", x)];
-	let data = RegressionDataBuilder::new().build_from(data).unwrap();
-	let formula = "Y ~ X";
+/// Worst case of the runs without components, base value followed by the
+/// slope of each varied component otherwise
+fn fit_max(
+	y: &[u32],
+	component_values: &[Vec<u32>],
+	varied: &[usize],
+) -> Result<Vec<f64>, ParseError> {
+	if varied.is_empty() {
+		Ok(vec![y.iter().copied().max().unwrap_or_default() as f64])
+	} else {
+		let y = y.iter().map(|x| *x as f64).collect();
+		regression(y, component_values, varied)
+	}
+}
+
+fn parse_measurement(
+	components: &[(Vec<u8>, u32, u32)],
+	measurement: &Measurement,
+	estimator: Estimator,
+) -> Result<BenchData, ParseError> {
+	// values are indexed by component, a short row can't be fitted
+	if let Some((run, values)) = measurement
+		.component_values
+		.iter()
+		.enumerate()
+		.find(|(_, values)| values.len() != components.len())
+	{
+		return Err(ParseError::ComponentValuesMismatch {
+			run,
+			expected: components.len(),
+			found: values.len(),
+		});
+	}
+
+	// components declared with a fixed value cannot be fitted
+	let varied: Vec<usize> = components
+		.iter()
+		.enumerate()
+		.filter(|(_, (_, low, high))| low < high)
+		.map(|(index, _)| index)
+		.collect();
+
+	// a component kept at the same value can't be told apart from the base value
+	for index in &varied {
+		let mut values: Vec<u32> = measurement.component_values.iter().map(|v| v[*index]).collect();
+		values.sort();
+		values.dedup();
+		if values.len() < 2 {
+			return Err(ParseError::ComponentNotVaried {
+				name: String::from_utf8_lossy(&components[*index].0).to_string(),
+				runs: measurement.component_values.len(),
+				required: COMPONENT_STEPS * components.len() as u32,
+			});
+		}
+	}
+
+	let proof_parameters = fit_max(
+		&measurement.proof_sizes,
+		&measurement.component_values,
+		&varied,
+	)?;
+	let read_parameters = fit_max(&measurement.reads, &measurement.component_values, &varied)?;
+	let write_parameters = fit_max(&measurement.writes, &measurement.component_values, &varied)?;
 
-	let model = FormulaRegressionBuilder::new().data(&data).formula(formula).fit().unwrap();
+	let keys = <Vec<(Vec<u8>, u32, u32)> as Decode>::decode(&mut &measurement.keys[..])?;
 
-	let mut total_reads = 0u32;
-	let mut total_writes = 0u32;
-	let keys = <Vec<(Vec<u8>, u32, u32)> as Decode>::decode(&mut &bencher.keys[..]).unwrap();
+	let key_bytes = <Vec<(Vec<u8>, u32, u32)> as Decode>::decode(&mut &measurement.bytes[..])?;
+	let bytes_read = key_bytes.iter().map(|(_, read, _)| *read as u64).sum();
+	let bytes_written = key_bytes.iter().map(|(_, _, written)| *written as u64).sum();
 
-	keys.iter().for_each(|(_prefix, reads, writes)| {
-		total_reads += reads;
-		total_writes += writes;
-	});
+	let accessed_keys =
+		<Vec<(Vec<u8>, u32, u32)> as Decode>::decode(&mut &measurement.accessed_keys[..])?;
+	let child_keys =
+		<Vec<(Vec<u8>, Vec<u8>, u32, u32)> as Decode>::decode(&mut &measurement.child_keys[..])?;
 
-	let intercepted_value = model.parameters()[0] as u64;
+	let stats = Stats::new(&measurement.elapses);
+	let estimator = if varied.is_empty() {
+		estimator
+	} else {
+		Estimator::Regression
+	};
 
-	let time = Duration::from_nanos(intercepted_value);
+	// the regression needs at least 2 runs, only fit it when it's used
+	let parameters = match estimator {
+		Estimator::Regression => {
+			let y = measurement.elapses.iter().map(|x| *x as f64).collect();
+			regression(y, &measurement.component_values, &varied)?
+		},
+		_ => vec![],
+	};
+	let time = match estimator {
+		Estimator::Regression => Duration::from_nanos(parameters[0].max(0.0) as u64),
+		Estimator::Mean => stats.mean,
+		Estimator::Median => stats.median,
+		Estimator::FilteredMean => stats.filtered_mean,
+		Estimator::Min => stats.min,
+	};
 
-	BenchData {
-		name: String::from_utf8_lossy(&bencher.method).to_string(),
+	let components = components
+		.iter()
+		.enumerate()
+		.map(|(index, (name, low, high))| {
+			let slope = |parameters: &[f64]| {
+				varied
+					.iter()
+					.position(|x| *x == index)
+					.map_or(0.0, |i| parameters[i + 1].max(0.0))
+			};
+			ComponentSlope {
+				name: String::from_utf8_lossy(name).to_string(),
+				low: *low,
+				high: *high,
+				slope: slope(&parameters),
+				proof_slope: slope(&proof_parameters),
+				read_slope: slope(&read_parameters),
+				write_slope: slope(&write_parameters),
+			}
+		})
+		.collect();
+
+	// counts are exact, the regression only adds rounding noise
+	let count = |x: f64| x.max(0.0).round() as u32;
+
+	Ok(BenchData {
+		name: String::from_utf8_lossy(&measurement.name).to_string(),
 		time,
-		reads: total_reads,
-		writes: total_writes,
+		proof_size: proof_parameters[0].max(0.0) as u64,
+		estimator,
+		stats,
+		storage_mode: measurement.storage_mode,
+		components,
+		reads: count(read_parameters[0]),
+		writes: count(write_parameters[0]),
+		bytes_read,
+		bytes_written,
 		keys,
-		warnings,
-	}
+		key_bytes,
+		accessed_keys,
+		child_keys,
+	})
 }
 
 fn get_package_name() -> String {
@@ -85,9 +479,41 @@ pub fn print_start(method: &str) {
 	print!("{} {:<60}...", green_bold("Bench"), cyan(method));
 	std::io::stdout().flush().unwrap();
 }
+
+pub fn print_dry_run(method: &str) {
+	println!(
+		"\r{} {:<60} {}",
+		green_bold("Bench"),
+		cyan(method),
+		green_bold("ok")
+	);
+}
+
+pub fn print_failed(failure: &BenchFailure) {
+	println!(
+		"\r{} {:<60} {}",
+		green_bold("Bench"),
+		cyan(&failure.method),
+		red_bold("FAILED")
+	);
+}
+
+pub fn print_failures(failures: &[BenchFailure]) {
+	for failure in failures {
+		let iteration = failure.iteration.map_or(String::new(), |x| format!(" in run {x}"));
+		println!(
+			"\n{} {}{}",
+			red_bold(&format!("{}:", failure.method)),
+			red_bold(&failure.kind.to_string()),
+			iteration
+		);
+		println!("{}", failure.message);
+	}
+}
+
 pub fn print_summary(data: &BenchData) {
 	print!(
-		"\r{} {:<60} {:>20} storage: {:<20}\n",
+		"\r{} {:<60} {:>20} storage: {:<20} bytes: {:<20} proof: {:>8}\n",
 		green_bold("Bench"),
 		cyan(&data.name),
 		green_bold(&format!("{:?}", data.time)),
@@ -96,61 +522,407 @@ pub fn print_summary(data: &BenchData) {
 			data.reads.to_string(),
 			data.writes.to_string()
 		)),
+		green_bold(&format!(
+			"[r: {}B, w: {}B]",
+			data.bytes_read, data.bytes_written
+		)),
+		green_bold(&format!("{}B", data.proof_size)),
+	);
+
+	println!(
+		"      {} median: {:?}, stddev: {:?}, p99: {:?}, outliers: {}, storage: {}",
+		cyan(&data.estimator.to_string()),
+		data.stats.median,
+		data.stats.stddev,
+		data.stats.p99,
+		data.stats.outliers,
+		data.storage_mode,
 	);
 
-	for warning in &data.warnings {
+	for component in &data.components {
+		println!(
+			"      {} {:<20} {:>20} {:>12} {:>16} per unit [{}, {}]",
+			cyan("+"),
+			cyan(&component.name),
+			green_bold(&format!("{:.3}ns", component.slope)),
+			green_bold(&format!("{:.1}B", component.proof_slope)),
+			green_bold(&format!(
+				"[r: {:.1}, w: {:.1}]",
+				component.read_slope, component.write_slope
+			)),
+			component.low,
+			component.high,
+		);
+	}
+}
+
+/// Print reads and writes of each accessed key
+pub fn print_keys(data: &BenchData, storage_infos: &[StorageMetadata]) {
+	let mut keys = data.accessed_keys.clone();
+	keys.sort();
+	for (key, reads, writes) in keys {
 		println!(
-			"{} {}",
-			yellow_bold("WARNING:"),
-			yellow_bold(&warning.to_string())
+			"      {} r: {:>2}, w: {:>2} {} 0x{}",
+			cyan("-"),
+			reads,
+			writes,
+			cyan(&key_name(&key, storage_infos)),
+			HexDisplay::from(&key),
+		);
+	}
+	for (child, key, reads, writes) in &data.child_keys {
+		println!(
+			"      {} r: {:>2}, w: {:>2} {} 0x{}",
+			cyan("-"),
+			reads,
+			writes,
+			cyan(&format!("Child 0x{}", HexDisplay::from(child))),
+			HexDisplay::from(key),
 		);
 	}
 }
 
-pub fn save_output_json(data: Vec<BenchData>, storage_infos: Vec<StorageMetadata>) {
+pub fn save_output_json(
+	data: Vec<BenchData>,
+	executor: ExecutorConfig,
+	storage_infos: Vec<StorageMetadata>,
+) {
 	let data = data
 		.into_iter()
 		.map(|x| {
+			let (estimated_proof_size, unbounded) = estimate_proof_size(&x.keys, &storage_infos);
+
 			let mut comments: Vec<String> = x
 				.keys
 				.into_iter()
 				.map(|(prefix, reads, writes)| {
-					if let Some(info) = storage_infos.iter().find(|x| x.prefix.eq(&prefix)) {
-						format!(
-							"{}::{} (r: {}, w: {})",
-							info.pallet_name, info.storage_name, reads, writes
-						)
-					} else {
-						format!(
-							"Unknown 0x{} (r: {}, w: {})",
-							HexDisplay::from(&prefix),
-							reads,
-							writes
-						)
-					}
+					format!(
+						"{} (r: {}, w: {})",
+						key_name(&prefix, &storage_infos),
+						reads,
+						writes
+					)
 				})
 				.collect();
 
+			let mut children: Vec<Vec<u8>> =
+				x.child_keys.iter().map(|(child, ..)| child.clone()).collect();
+			children.sort();
+			children.dedup();
+
+			comments.extend(
+				x.child_keys.into_iter().map(|(child, prefix, reads, writes)| {
+					format!(
+						"Child 0x{} 0x{} (r: {}, w: {})",
+						HexDisplay::from(&child),
+						HexDisplay::from(&prefix),
+						reads,
+						writes
+					)
+				}),
+			);
+
 			comments.sort();
+			comments.extend(unbounded.into_iter().map(|prefix| {
+				match storage_infos.iter().find(|x| x.prefix.eq(&prefix)) {
+					Some(info) => format!(
+						"{}::{} has no max size, not included in proof size estimate",
+						info.pallet_name, info.storage_name
+					),
+					None => format!(
+						"{} not included in proof size estimate",
+						key_name(&prefix, &storage_infos)
+					),
+				}
+			}));
+			comments.extend(children.into_iter().map(|child| {
+				format!(
+					"Child 0x{} not included in proof size estimate",
+					HexDisplay::from(&child)
+				)
+			}));
 
 			BenchDataOutput {
 				name: x.name,
 				weight: x.time.as_nanos() as u64 * 1_000,
+				proof_size: x.proof_size,
+				estimated_proof_size,
+				storage_mode: x.storage_mode,
+				components: x
+					.components
+					.into_iter()
+					.map(|c| ComponentOutput {
+						name: c.name,
+						weight: (c.slope * 1_000.0) as u64,
+						proof_size: c.proof_slope.ceil() as u64,
+						reads: c.read_slope.round() as u32,
+						writes: c.write_slope.round() as u32,
+					})
+					.collect(),
 				reads: x.reads,
 				writes: x.writes,
+				bytes_read: x.bytes_read,
+				bytes_written: x.bytes_written,
 				comments,
+				executor,
 			}
 		})
 		.collect::<Vec<BenchDataOutput>>();
 
+	let json_path = write_json("bench_data", &data);
+	println!("\nOutput JSON file:\n{json_path}");
+}
+
+/// Save failure details next to the bench data
+pub fn save_failures_json(failures: &[BenchFailure]) {
+	let json_path = write_json("bench_failures", &failures);
+	println!("\nFailures JSON file:\n{json_path}");
+}
+
+/// Save the storage accesses of a traced run of bench `method`
+pub fn save_trace(method: &str, trace: &[TraceEntry], format: TraceFormat) {
+	let name = format!("{method}_trace");
+	let path = match format {
+		TraceFormat::Json => write_json(&name, &trace),
+		TraceFormat::Csv => write_csv(&name, trace),
+	};
+	println!(
+		"      {} {} accesses traced to {path}",
+		cyan("trace"),
+		trace.len()
+	);
+}
+
+fn output_path(name: &str, extension: &str) -> String {
 	let outdir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
 	let pkg_name = get_package_name().replace('-', "_");
-	let json_path = format!("{outdir}/target/{pkg_name}_bench_data.json");
-	let mut writer =
-		std::io::BufWriter::new(std::fs::File::create(std::path::Path::new(&json_path)).unwrap());
-	serde_json::to_writer_pretty(&mut writer, &data).unwrap();
+	format!("{outdir}/target/{pkg_name}_{name}.{extension}")
+}
+
+fn create_file(path: &str) -> std::io::BufWriter<std::fs::File> {
+	std::io::BufWriter::new(std::fs::File::create(std::path::Path::new(path)).unwrap())
+}
+
+fn write_json(name: &str, data: &impl Serialize) -> String {
+	let json_path = output_path(name, "json");
+	let mut writer = create_file(&json_path);
+	serde_json::to_writer_pretty(&mut writer, data).unwrap();
 	writer.write_all(b"\n").unwrap();
 	writer.flush().unwrap();
+	json_path
+}
 
-	println!("\nOutput JSON file:\n{json_path}");
+fn write_csv(name: &str, trace: &[TraceEntry]) -> String {
+	let csv_path = output_path(name, "csv");
+	let mut writer = create_file(&csv_path);
+	writeln!(
+		writer,
+		"op,child,key,redundant,access,depth,whitelisted,size"
+	)
+	.unwrap();
+	for entry in trace {
+		writeln!(
+			writer,
+			"{},0x{},0x{},{},{},{},{},{}",
+			entry.op,
+			HexDisplay::from(&entry.child),
+			HexDisplay::from(&entry.key),
+			entry.redundant,
+			entry.access,
+			entry.depth,
+			entry.whitelisted,
+			entry.size
+		)
+		.unwrap();
+	}
+	writer.flush().unwrap();
+	csv_path
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+
+	#[test]
+	fn percentile_uses_nearest_rank() {
+		let sorted: Vec<f64> = (1..=10).map(|x| x as f64).collect();
+		assert_eq!(percentile(&sorted, 0.0), 1.0);
+		assert_eq!(percentile(&sorted, 10.0), 1.0);
+		assert_eq!(percentile(&sorted, 11.0), 2.0);
+		assert_eq!(percentile(&sorted, 50.0), 5.0);
+		assert_eq!(percentile(&sorted, 95.0), 10.0);
+		assert_eq!(percentile(&sorted, 100.0), 10.0);
+		assert_eq!(percentile(&[7.0], 99.0), 7.0);
+	}
+
+	#[test]
+	fn stats_of_runs() {
+		let stats = Stats::new(&[40, 10, 30, 20]);
+		assert_eq!(stats.min, Duration::from_nanos(10));
+		assert_eq!(stats.max, Duration::from_nanos(40));
+		assert_eq!(stats.mean, Duration::from_nanos(25));
+		assert_eq!(stats.median, Duration::from_nanos(20));
+		assert_eq!(stats.p99, Duration::from_nanos(40));
+		// population standard deviation, sqrt(125)
+		assert_eq!(stats.stddev, Duration::from_nanos(11));
+		assert_eq!(stats.filtered_mean, stats.mean);
+		assert_eq!(stats.outliers, 0);
+	}
+
+	#[test]
+	fn stats_reject_outliers() {
+		let stats = Stats::new(&[10, 11, 9, 10, 12, 10, 1000]);
+		assert_eq!(stats.outliers, 1);
+		assert_eq!(stats.max, Duration::from_nanos(1000));
+		assert_eq!(stats.filtered_mean, Duration::from_nanos(10));
+	}
+
+	#[test]
+	fn stats_of_no_runs() {
+		let stats = Stats::new(&[]);
+		assert_eq!(stats.mean, Duration::ZERO);
+		assert_eq!(stats.outliers, 0);
+	}
+
+	/// Record `runs` runs of `bench`, each costing `cost` of its component
+	/// values in time, proof size, reads and writes
+	fn synthesize(
+		runs: u32,
+		bench: impl Fn(&mut Bencher),
+		cost: impl Fn(&[u32]) -> u32,
+	) -> Bencher {
+		let mut b = Bencher::with_name("bench");
+		for _ in 0..runs {
+			b.before_run();
+			bench(&mut b);
+		}
+		let no_keys = Vec::<(Vec<u8>, u32, u32)>::new().encode();
+		for measurement in &mut b.measurements {
+			for values in &measurement.component_values {
+				let cost = cost(values);
+				measurement.elapses.push(cost as u128);
+				measurement.proof_sizes.push(cost);
+				measurement.reads.push(cost);
+				measurement.writes.push(cost);
+			}
+			measurement.keys = no_keys.clone();
+			measurement.bytes = no_keys.clone();
+			measurement.accessed_keys = no_keys.clone();
+			measurement.child_keys = Vec::<(Vec<u8>, Vec<u8>, u32, u32)>::new().encode();
+		}
+		b
+	}
+
+	fn assert_close(x: f64, expected: f64) {
+		assert!((x - expected).abs() < 1e-6, "{x} != {expected}");
+	}
+
+	#[test]
+	fn parse_fits_base_and_slopes() {
+		let b = synthesize(
+			2 * COMPONENT_STEPS,
+			|b| {
+				b.component("n", 1..=10);
+				b.component("m", 0..=90);
+				b.bench(|| ());
+			},
+			|values| 300 + 2 * values[0] + 5 * values[1],
+		);
+		let data = parse(b.encode(), Estimator::Median).unwrap();
+		assert_eq!(data.len(), 1);
+		let data = &data[0];
+		assert_eq!(data.estimator, Estimator::Regression);
+		assert!(data.time.as_nanos().abs_diff(300) <= 1);
+		assert!(data.proof_size.abs_diff(300) <= 1);
+		assert_eq!(data.reads, 300);
+		assert_eq!(data.writes, 300);
+		let (n, m) = (&data.components[0], &data.components[1]);
+		assert_eq!((n.name.as_str(), n.low, n.high), ("n", 1, 10));
+		for (component, slope) in [(n, 2.0), (m, 5.0)] {
+			assert_close(component.slope, slope);
+			assert_close(component.proof_slope, slope);
+			assert_close(component.read_slope, slope);
+			assert_close(component.write_slope, slope);
+		}
+	}
+
+	#[test]
+	fn parse_requires_every_component_varied() {
+		// `m` is kept at its maximum until the second block of runs
+		let b = synthesize(
+			COMPONENT_STEPS,
+			|b| {
+				b.component("n", 1..=10);
+				b.component("m", 0..=90);
+				b.bench(|| ());
+			},
+			|values| 2 * values[0] + 5 * values[1],
+		);
+		match parse(b.encode(), Estimator::Median) {
+			Err(ParseError::ComponentNotVaried {
+				name,
+				runs,
+				required,
+			}) => {
+				assert_eq!(name, "m");
+				assert_eq!(runs, COMPONENT_STEPS as usize);
+				assert_eq!(required, 2 * COMPONENT_STEPS);
+			},
+			other => panic!("unexpected result: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn parse_keeps_fixed_components_in_base() {
+		let b = synthesize(
+			2 * COMPONENT_STEPS,
+			|b| {
+				b.component("k", 4..=4);
+				b.component("n", 1..=10);
+				b.bench(|| ());
+			},
+			|values| 100 * values[0] + 2 * values[1],
+		);
+		let data = &parse(b.encode(), Estimator::Median).unwrap()[0];
+		assert_eq!(data.reads, 400);
+		assert!(data.proof_size.abs_diff(400) <= 1);
+		let (k, n) = (&data.components[0], &data.components[1]);
+		assert_eq!((k.low, k.high, k.slope, k.read_slope), (4, 4, 0.0, 0.0));
+		assert_close(n.read_slope, 2.0);
+	}
+
+	#[test]
+	fn parse_rejects_measurements_missing_components() {
+		// `setup` is recorded before `n` is declared
+		let b = synthesize(
+			2 * COMPONENT_STEPS,
+			|b| {
+				b.bench_named("setup", || ());
+				b.component("n", 1..=10);
+				b.bench(|| ());
+			},
+			|_| 1,
+		);
+		match parse(b.encode(), Estimator::Median) {
+			Err(ParseError::ComponentValuesMismatch {
+				run,
+				expected,
+				found,
+			}) => assert_eq!((run, expected, found), (0, 1, 0)),
+			other => panic!("unexpected result: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn serde_uses_kebab_case() {
+		assert_eq!(
+			serde_json::to_string(&Estimator::FilteredMean).unwrap(),
+			r#""filtered-mean""#
+		);
+		assert_eq!(
+			serde_json::from_str::<TraceFormat>(r#""csv""#).unwrap(),
+			TraceFormat::Csv
+		);
+	}
 }
@@ -25,7 +25,11 @@ pub mod bench_runner;
 #[cfg(feature = "std")]
 pub mod build_wasm;
 #[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
 pub mod handler;
+#[cfg(feature = "std")]
+pub mod state;
 
 #[cfg(feature = "std")]
 mod bench_ext;
@@ -34,4 +38,4 @@ pub mod colorize;
 #[cfg(feature = "std")]
 mod tracker;
 
-pub use wasm_bencher_procedural::benchmarkable;
+pub use wasm_bencher_procedural::{benchmarkable, skip};
@@ -25,15 +25,33 @@
 /// wasm_bencher::main!();
 /// # or, run benches with storage info, required by `wasm-bencher` to generate output json
 /// wasm_bencher::main!({ your_module::mock::AllPalletsWithSystem::storage_info() }));
+/// # start each bench from genesis storage instead of empty storage
+/// wasm_bencher::main!(
+///     { your_module::mock::AllPalletsWithSystem::storage_info() },
+///     genesis: { your_module::mock::RuntimeGenesisConfig::default().build_storage().unwrap() }
+/// );
+/// # whitelist keys in all benches, e.g. the `frame_system` keys declared with `#[pallet::whitelist_storage]`
+/// wasm_bencher::main!(
+///     { your_module::mock::AllPalletsWithSystem::storage_info() },
+///     whitelist: { your_module::mock::AllPalletsWithSystem::whitelisted_storage_keys() }
+/// );
+/// # whitelist all keys under prefixes in all benches, e.g. every entry of a storage map
+/// wasm_bencher::main!(
+///     { your_module::mock::AllPalletsWithSystem::storage_info() },
+///     whitelist_prefixes: { vec![frame_system::Account::<your_module::mock::Runtime>::final_prefix().to_vec()] }
+/// );
 /// ```
 ///
+/// The genesis storage can also be loaded from a raw chain spec with
+/// `--genesis path/to/raw-spec.json`, on top of the `genesis` block.
+///
 /// Define benches
 ///
 /// Create a file `src/benches.rs`
 /// ```ignore
 /// #!#[cfg(feature = "wasm-bench")]
 ///
-/// use wasm_bencher::{Bencher, benches};
+/// use wasm_bencher::{Bencher, StorageMode, benches};
 /// use crate::mock::*;
 ///
 /// fn foo(b: &mut Bencher) {
@@ -54,7 +72,35 @@
 ///     });
 /// }
 ///
-/// benches!(foo, bar); // Tests are generated automatically
+/// fn bar_or_qux(b: &mut Bencher) {
+///     // optional. record sibling code paths sharing the same setup as separate benches
+///     b.bench_named("bar", || YourModule::bar());
+///     b.bench_named("qux", || YourModule::qux());
+/// }
+///
+/// fn foo_warm(b: &mut Bencher) {
+///     // optional. measure with the keys accessed by the previous run already cached
+///     b.storage_mode(StorageMode::Warm).bench(|| YourModule::foo());
+/// }
+///
+/// fn baz(b: &mut Bencher) {
+///     // optional. declare components to get a base weight plus a weight per unit
+///     let n = b.component("n", 1..=1_000);
+///     b.bench(|| {
+///         YourModule::baz(n);
+///     });
+/// }
+///
+/// benches!(foo, bar, bar_or_qux, foo_warm, baz); // Tests are generated automatically
+///
+/// // optional. override the number of measured and warmup runs of a bench
+/// benches!(foo, bar = { iterations: 50, warmup: 5 }, baz);
+///
+/// // optional. whitelist keys in all benches of this file
+/// benches!(foo, bar, baz; whitelist: { AllPalletsWithSystem::whitelisted_storage_keys() });
+///
+/// // optional. whitelist all keys under prefixes in all benches of this file
+/// benches!(foo, bar, baz; whitelist_prefixes: { sp_std::vec![frame_system::Account::<Runtime>::final_prefix().to_vec()] });
 /// ```
 /// Update `src/lib.rs`
 /// ```ignore
@@ -64,13 +110,37 @@
 /// ```
 ///
 /// Run benchmarking: `cargo bench --features=wasm-bench`
+/// Override iterations of all benches: `cargo bench --features=wasm-bench -- --iterations 100 --warmup 10`
+/// Select the estimator of bench times: `cargo bench --features=wasm-bench -- --estimator median`
+/// Run only some benches: `cargo bench --features=wasm-bench -- set_ --skip whitelist`
+/// Match bench names exactly: `cargo bench --features=wasm-bench -- --exact set_foo`
+/// List benches: `cargo bench --features=wasm-bench -- --list`
+/// Run each bench once as a smoke test: `cargo bench --features=wasm-bench -- --dry-run`
+/// Print reads and writes of each accessed key: `cargo bench --features=wasm-bench -- --verbose`
+/// Trace storage accesses of the first measured run of a bench, in `target/<package>_<bench>_trace.json`: `cargo bench --features=wasm-bench -- --exact set_foo --warmup 0 --trace 1 --trace-format json`
+/// Bench against a snapshot of chain state, saved as the `[key, value]` pairs returned by `state_getPairs`: `cargo bench --features=wasm-bench -- --snapshot snapshot.json`
 /// Run benchmark auto-generated tests: `cargo test --features=wasm-bench`
 #[macro_export]
 macro_rules! benches {
-    ($($method:path),+) => {
+    (
+        $($method:path $(= { $($key:ident: $value:expr),* $(,)? })?),+ $(,)?
+        $(; $(whitelist: $whitelist:block)? $(,)? $(whitelist_prefixes: $whitelist_prefixes:block)? $(,)?)?
+    ) => {
         #[cfg(feature = "wasm-bench")]
         $crate::paste::item! {
             use $crate::sp_std::vec::Vec;
+
+            // keys whitelisted in all benches
+            #[allow(unused_variables)]
+            fn __whitelist_keys(bencher: &mut $crate::Bencher) {
+                $($(bencher.whitelist_keys($whitelist);)?)?
+                $($(
+                    for prefix in $whitelist_prefixes {
+                        bencher.whitelist_prefix(prefix, true, true);
+                    }
+                )?)?
+            }
+
             $crate::sp_core::wasm_export_functions! {
                 // list of bench methods
                 fn available_bench_methods() -> Vec<&str> {
@@ -84,11 +154,22 @@ macro_rules! benches {
 
                 // wrapped bench methods to run
                 $(
-                    fn [<bench_ $method>] () -> $crate::Bencher {
+                    fn [<bench_ $method>] (config: $crate::BenchConfig) -> $crate::Bencher {
                         let name = stringify!($method);
                         let mut bencher = $crate::Bencher::with_name(name);
+                        __whitelist_keys(&mut bencher);
+                        let config = config.or($crate::BenchConfig {
+                            $($($key: Some($value),)*)?
+                            ..Default::default()
+                        });
 
-                        for _ in 0..1_000 {
+                        for _ in 0..config.warmup() {
+                            bencher.before_run();
+                            $method(&mut bencher);
+                        }
+                        bencher.reset();
+
+                        for _ in 0..config.iterations() {
                             bencher.before_run();
                             $method(&mut bencher);
                         }
@@ -105,7 +186,7 @@ macro_rules! benches {
         #[panic_handler]
         fn panic_handler(info: &::core::panic::PanicInfo) -> ! {
             let message = $crate::sp_std::alloc::format!("{}", info);
-            $crate::bench::print_error(message.as_bytes().to_vec());
+            $crate::bench::record_panic(message.as_bytes().to_vec());
             unsafe {core::arch::wasm32::unreachable(); }
         }
 
@@ -131,60 +212,133 @@ macro_rules! benches {
 #[macro_export]
 macro_rules! main {
 	(
-        $($storage_info:block)?
+        $($storage_info:block)? $(,)? $(genesis: $genesis:block)? $(,)? $(whitelist: $whitelist:block)? $(,)? $(whitelist_prefixes: $whitelist_prefixes:block)? $(,)?
     ) => {
 		#[cfg(all(feature = "std", feature = "wasm-bench"))]
 		pub fn main() -> std::io::Result<()> {
+            let options = match $crate::cli::Options::from_args() {
+                Ok(options) => options,
+                Err(error) => {
+                    eprintln!("{}", $crate::colorize::red_bold(&error.to_string()));
+                    std::process::exit(2);
+                }
+            };
+
             // build project to wasm
 			let wasm = $crate::build_wasm::build()?;
+            let mut genesis = $crate::sp_core::storage::Storage::default();
+            $(genesis = $genesis;)?
+            if let Some(path) = &options.genesis {
+                $crate::state::extend(&mut genesis, $crate::state::load_raw_chain_spec(path)?);
+            }
+            if let Some(path) = &options.snapshot {
+                let snapshot = $crate::state::load_snapshot(path)?;
+                println!("Loaded {} keys from snapshot {path}", snapshot.top.len());
+                $crate::state::extend(&mut genesis, snapshot);
+            }
+
+            use ::frame_support::traits::StorageInfoTrait;
+            let mut storage_info: Vec<::frame_support::traits::StorageInfo> = vec![];
+            $(storage_info = $storage_info;)?
+            let storage_infos: Vec<$crate::handler::StorageMetadata> = storage_info.into_iter().map(|x| {
+                $crate::handler::StorageMetadata {
+                    pallet_name: String::from_utf8_lossy(&x.pallet_name).to_string(),
+                    storage_name: String::from_utf8_lossy(&x.storage_name).to_string(),
+                    prefix: x.prefix,
+                    max_values: x.max_values,
+                    max_size: x.max_size,
+                }
+            }).collect();
+
+            let mut whitelist: Vec<$crate::sp_core::storage::TrackedStorageKey> = vec![];
+            $(whitelist = $whitelist;)?
+            let mut whitelisted_prefixes: Vec<Vec<u8>> = vec![];
+            $(whitelisted_prefixes = $whitelist_prefixes;)?
+
+            let runner = $crate::bench_runner::BenchRunner::with_config(&wasm[..], options.executor)
+                .with_genesis(genesis)
+                .with_whitelist(whitelist)
+                .with_whitelisted_prefixes(whitelisted_prefixes)
+                .with_known_prefixes(storage_infos.iter().map(|x| x.prefix.clone()).collect());
 
             // get list of bench methods
-            let methods = $crate::bench_runner::run(&wasm[..], "available_bench_methods", &[]).unwrap();
+            let methods = runner.run("available_bench_methods", &[]).map_err(|e| std::io::Error::other(e.to_string()))?;
             let bench_methods = <Vec<String> as $crate::codec::Decode>::decode(&mut &methods[..]).unwrap();
+            let bench_methods: Vec<String> = bench_methods.into_iter().filter(|x| options.is_selected(x)).collect();
+
+            if options.list {
+                for method in bench_methods {
+                    println!("{method}: bench");
+                }
+                return Ok(());
+            }
+
             println!("\nRunning {} benches\n", bench_methods.len());
 
             let mut results: Vec<$crate::handler::BenchData> = vec![];
-            let mut failed: Vec<String> = vec![];
+            let mut passed = 0usize;
+            let mut failed: Vec<$crate::bench_runner::BenchFailure> = vec![];
 
             // bench each method
             for method in bench_methods {
                 $crate::handler::print_start(&method);
-                match $crate::bench_runner::run(&wasm[..], &format!("bench_{method}"), &[])
+                let call_data = $crate::codec::Encode::encode(&options.config);
+                let (result, trace) = match options.trace {
+                    Some(run) => match runner.trace(&format!("bench_{method}"), &call_data, run) {
+                        Ok((output, trace)) => (Ok(output), Some(trace)),
+                        Err(failure) => (Err(failure), None),
+                    },
+                    None => (runner.run(&format!("bench_{method}"), &call_data), None),
+                };
+                match result
                 {
-                    Ok(output) => {
-                        let data = $crate::handler::parse(output);
-                        $crate::handler::print_summary(&data);
-                        results.push(data);
+                    Ok(_) if options.dry_run => {
+                        $crate::handler::print_dry_run(&method);
+                        passed += 1;
                     }
-                    Err(err) => {
-                        failed.push(method);
+                    Ok(output) => match $crate::handler::parse(output, options.estimator) {
+                        Ok(data) => {
+                            for data in data {
+                                $crate::handler::print_summary(&data);
+                                if options.verbose {
+                                    $crate::handler::print_keys(&data, &storage_infos);
+                                }
+                                results.push(data);
+                            }
+                            passed += 1;
+                        }
+                        Err(error) => {
+                            let failure = $crate::bench_runner::BenchFailure::analysis(&method, error);
+                            $crate::handler::print_failed(&failure);
+                            failed.push(failure);
+                        }
+                    },
+                    Err(failure) => {
+                        $crate::handler::print_failed(&failure);
+                        failed.push(failure);
                     }
                 };
+                if let Some(trace) = trace {
+                    $crate::handler::save_trace(&method, &trace, options.trace_format);
+                }
             }
 
             // print summary
             if failed.is_empty() {
-                println!("\n✅ Complete: {}", $crate::colorize::green_bold(&format!("{} passed", results.len())));
+                println!("\n✅ Complete: {}", $crate::colorize::green_bold(&format!("{} passed", passed)));
             } else {
-                println!("\n❌ Finished with errors: {}, {}", $crate::colorize::green_bold(&format!("{} passed", results.len())), $crate::colorize::red_bold(&format!("{} failed", failed.len())));
+                $crate::handler::print_failures(&failed);
+                println!("\n❌ Finished with errors: {}, {}", $crate::colorize::green_bold(&format!("{} passed", passed)), $crate::colorize::red_bold(&format!("{} failed", failed.len())));
+                if options.json {
+                    $crate::handler::save_failures_json(&failed);
+                }
                 std::process::exit(1);
             }
 
             // save output to json if `json` arg is passed
-            if std::env::args().find(|x| x.eq("json")).is_some() {
-                use ::frame_support::traits::StorageInfoTrait;
-                let mut storage_info: Vec<::frame_support::traits::StorageInfo> = vec![];
-                $(storage_info = $storage_info;)?
-                assert!(!storage_info.is_empty(), "Cannot find storage info, please include `AllPalletsWithSystem` generated by `frame_support::construct_runtime`");
-                $crate::handler::save_output_json(results, storage_info.into_iter().map(|x| {
-                    $crate::handler::StorageMetadata {
-                        pallet_name: String::from_utf8_lossy(&x.pallet_name).to_string(),
-                        storage_name: String::from_utf8_lossy(&x.storage_name).to_string(),
-                        prefix: x.prefix,
-                        max_values: x.max_values,
-                        max_size: x.max_size,
-                    }
-                }).collect());
+            if options.json && !options.dry_run {
+                assert!(!storage_infos.is_empty(), "Cannot find storage info, please include `AllPalletsWithSystem` generated by `frame_support::construct_runtime`");
+                $crate::handler::save_output_json(results, options.executor, storage_infos);
             }
 
 			Ok(())
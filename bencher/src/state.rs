@@ -0,0 +1,153 @@
+use serde::Deserialize;
+use sp_core::storage::{ChildInfo, Storage, StorageChild, StorageData, StorageKey};
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawGenesis {
+	top: BTreeMap<StorageKey, StorageData>,
+	#[serde(default)]
+	children_default: BTreeMap<StorageKey, BTreeMap<StorageKey, StorageData>>,
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+	raw: RawGenesis,
+}
+
+#[derive(Deserialize)]
+struct RawChainSpec {
+	genesis: Genesis,
+}
+
+/// Load the genesis storage of a raw chain spec, as generated by
+/// `build-spec --raw`.
+pub fn load_raw_chain_spec(path: impl AsRef<Path>) -> std::io::Result<Storage> {
+	let reader = BufReader::new(File::open(path)?);
+	let RawChainSpec { genesis } = serde_json::from_reader(reader)?;
+
+	let top = genesis.raw.top.into_iter().map(|(k, v)| (k.0, v.0)).collect();
+	let children_default = genesis
+		.raw
+		.children_default
+		.into_iter()
+		.map(|(storage_key, data)| {
+			let child = StorageChild {
+				data: data.into_iter().map(|(k, v)| (k.0, v.0)).collect(),
+				child_info: ChildInfo::new_default(&storage_key.0),
+			};
+			(storage_key.0, child)
+		})
+		.collect();
+
+	Ok(Storage {
+		top,
+		children_default,
+	})
+}
+
+/// Load a state snapshot, a JSON list of `[key, value]` hex pairs as
+/// returned by the `state_getPairs` RPC.
+pub fn load_snapshot(path: impl AsRef<Path>) -> std::io::Result<Storage> {
+	let reader = BufReader::new(File::open(path)?);
+	let pairs: Vec<(StorageKey, StorageData)> = serde_json::from_reader(reader)?;
+
+	Ok(Storage {
+		top: pairs.into_iter().map(|(k, v)| (k.0, v.0)).collect(),
+		children_default: Default::default(),
+	})
+}
+
+/// Add the keys of `other` to `storage`, replacing existing values.
+pub fn extend(storage: &mut Storage, other: Storage) {
+	storage.top.extend(other.top);
+	for (storage_key, child) in other.children_default {
+		match storage.children_default.get_mut(&storage_key) {
+			Some(existing) => existing.data.extend(child.data),
+			None => {
+				storage.children_default.insert(storage_key, child);
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	fn fixture(json: &str) -> tempfile::NamedTempFile {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(json.as_bytes()).unwrap();
+		file
+	}
+
+	#[test]
+	fn loads_raw_chain_spec() {
+		let file = fixture(
+			r#"{
+				"name": "Development",
+				"genesis": {
+					"raw": {
+						"top": { "0x0102": "0x0a0b", "0x03": "0x" },
+						"childrenDefault": { "0x04": { "0x05": "0x06" } }
+					}
+				}
+			}"#,
+		);
+		let storage = load_raw_chain_spec(file.path()).unwrap();
+
+		assert_eq!(
+			storage.top,
+			BTreeMap::from([(vec![1, 2], vec![10, 11]), (vec![3], vec![])])
+		);
+		let child = &storage.children_default[&vec![4]];
+		assert_eq!(child.data, BTreeMap::from([(vec![5], vec![6])]));
+		assert_eq!(child.child_info, ChildInfo::new_default(&[4]));
+	}
+
+	#[test]
+	fn loads_raw_chain_spec_without_children() {
+		let file = fixture(r#"{ "genesis": { "raw": { "top": { "0x01": "0x02" } } } }"#);
+		let storage = load_raw_chain_spec(file.path()).unwrap();
+
+		assert_eq!(storage.top, BTreeMap::from([(vec![1], vec![2])]));
+		assert!(storage.children_default.is_empty());
+	}
+
+	#[test]
+	fn rejects_chain_spec_without_raw_genesis() {
+		let file = fixture(r#"{ "genesis": { "runtime": {} } }"#);
+		assert!(load_raw_chain_spec(file.path()).is_err());
+	}
+
+	#[test]
+	fn loads_snapshot() {
+		let file = fixture(r#"[["0x01", "0x0203"], ["0x04", "0x05"]]"#);
+		let storage = load_snapshot(file.path()).unwrap();
+
+		assert_eq!(
+			storage.top,
+			BTreeMap::from([(vec![1], vec![2, 3]), (vec![4], vec![5])])
+		);
+		assert!(storage.children_default.is_empty());
+	}
+
+	#[test]
+	fn extends_storage() {
+		let mut storage = load_raw_chain_spec(
+			fixture(r#"{ "genesis": { "raw": { "top": { "0x01": "0x01", "0x02": "0x02" } } } }"#)
+				.path(),
+		)
+		.unwrap();
+		extend(
+			&mut storage,
+			load_snapshot(fixture(r#"[["0x02", "0x0a"], ["0x03", "0x03"]]"#).path()).unwrap(),
+		);
+
+		assert_eq!(
+			storage.top,
+			BTreeMap::from([(vec![1], vec![1]), (vec![2], vec![10]), (vec![3], vec![3])])
+		);
+	}
+}
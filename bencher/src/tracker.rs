@@ -1,12 +1,17 @@
-use codec::{Decode, Encode};
+use codec::Encode;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use sp_externalities::MultiRemovalResults;
 use sp_state_machine::StorageKey;
 use sp_storage::ChildInfo;
-use std::{collections::HashMap, sync::Arc, time::Instant};
-
-#[derive(PartialEq, Eq, Default)]
-enum AccessType {
+use std::{collections::HashMap, hash::Hash, sync::Arc, time::Instant};
+use strum::Display;
+
+/// How an access of a key is counted
+#[derive(Serialize, Deserialize, PartialEq, Eq, Default, Debug, Clone, Copy, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum AccessType {
 	#[default]
 	None,
 	Redundant,
@@ -29,10 +34,14 @@ impl AccessType {
 struct AccessInfo {
 	pub read: AccessType,
 	pub written: AccessType,
+	/// Length of the value read.
+	pub bytes_read: u32,
+	/// Length of the last value written.
+	pub bytes_written: u32,
 }
 
 impl AccessInfo {
-	fn read(redundant: bool) -> Self {
+	fn read(redundant: bool, len: u32) -> Self {
 		let read = if redundant {
 			AccessType::Redundant
 		} else {
@@ -41,10 +50,12 @@ impl AccessInfo {
 		Self {
 			read,
 			written: AccessType::None,
+			bytes_read: len,
+			bytes_written: 0,
 		}
 	}
 
-	fn written(redundant: bool) -> Self {
+	fn written(redundant: bool, len: u32) -> Self {
 		let written = if redundant {
 			AccessType::Redundant
 		} else {
@@ -53,6 +64,8 @@ impl AccessInfo {
 		Self {
 			read: AccessType::Redundant,
 			written,
+			bytes_read: 0,
+			bytes_written: len,
 		}
 	}
 
@@ -68,6 +81,7 @@ impl AccessInfo {
 			} else {
 				AccessType::None
 			},
+			..Default::default()
 		}
 	}
 }
@@ -76,44 +90,248 @@ impl AccessInfo {
 struct AccessReport {
 	pub read: u32,
 	pub written: u32,
+	pub bytes_read: u32,
+	pub bytes_written: u32,
+}
+
+/// Child trie key, the child storage key followed by the key in the child trie
+type ChildKey = (StorageKey, StorageKey);
+
+/// Kind of a traced storage access
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum TraceOp {
+	Read,
+	Write,
+	ClearPrefix,
+}
+
+/// A storage access of the traced run, in the order they happened
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct TraceEntry {
+	pub op: TraceOp,
+	/// Child storage key, empty for the main trie.
+	#[serde(with = "sp_core::bytes")]
+	pub child: StorageKey,
+	/// Accessed key, or the removed prefix.
+	#[serde(with = "sp_core::bytes")]
+	pub key: StorageKey,
+	/// Whether the access happened in a nested benchmarkable call.
+	pub redundant: bool,
+	/// How the key is counted after this access.
+	pub access: AccessType,
+	/// Depth of benchmarkable calls.
+	pub depth: u32,
+	pub whitelisted: bool,
+	/// Length of the value read or written, keys removed for `clear-prefix`.
+	pub size: u32,
+}
+
+fn track_read<K: Eq + Hash>(keys: &mut HashMap<K, AccessInfo>, key: K, redundant: bool, len: u32) {
+	match keys.get_mut(&key) {
+		Some(info) => {
+			if redundant {
+				return;
+			}
+			if info.written.is_important() {
+				return;
+			}
+			info.read.mark_important();
+			info.bytes_read = len;
+		},
+		None => {
+			keys.insert(key, AccessInfo::read(redundant, len));
+		},
+	};
+}
+
+fn track_write<K: Eq + Hash>(keys: &mut HashMap<K, AccessInfo>, key: K, redundant: bool, len: u32) {
+	match keys.get_mut(&key) {
+		Some(info) => {
+			if redundant {
+				return;
+			}
+			info.written.mark_important();
+			info.bytes_written = len;
+		},
+		None => {
+			keys.insert(key, AccessInfo::written(redundant, len));
+		},
+	};
+}
+
+/// Appended items add up to the bytes written to the key
+fn track_append<K: Eq + Hash>(
+	keys: &mut HashMap<K, AccessInfo>,
+	key: K,
+	redundant: bool,
+	len: u32,
+) {
+	match keys.get_mut(&key) {
+		Some(info) => {
+			if redundant {
+				return;
+			}
+			info.written.mark_important();
+			info.bytes_written = info.bytes_written.saturating_add(len);
+		},
+		None => {
+			keys.insert(key, AccessInfo::written(redundant, len));
+		},
+	};
+}
+
+/// Each iteration of a removal reads a key, each removed key is a write
+fn track_clear_prefix<K: Eq + Hash>(
+	clear_prefixes: &mut HashMap<K, AccessReport>,
+	key: K,
+	removal: &MultiRemovalResults,
+	(read_whitelisted, write_whitelisted): (bool, bool),
+) {
+	let report = clear_prefixes.entry(key).or_default();
+	if !read_whitelisted {
+		report.read += removal.loops;
+	}
+	if !write_whitelisted {
+		report.written += removal.unique;
+	}
+}
+
+/// Count important reads and writes of `keys` and removals per prefix
+fn summarize<K: Eq + Hash>(
+	keys: &HashMap<K, AccessInfo>,
+	clear_prefixes: &HashMap<K, AccessReport>,
+	prefix: impl Fn(&K) -> K,
+) -> HashMap<K, AccessReport> {
+	let mut summary = HashMap::<K, AccessReport>::new();
+
+	keys.iter().for_each(|(key, info)| {
+		let mut report = AccessReport::default();
+		if info.read.is_important() {
+			report.read += 1;
+			report.bytes_read += info.bytes_read;
+		}
+		if info.written.is_important() {
+			report.written += 1;
+			report.bytes_written += info.bytes_written;
+		}
+		if report.read + report.written > 0 {
+			let entry = summary.entry(prefix(key)).or_default();
+			entry.read += report.read;
+			entry.written += report.written;
+			entry.bytes_read += report.bytes_read;
+			entry.bytes_written += report.bytes_written;
+		}
+	});
+
+	clear_prefixes.iter().for_each(|(key, removal)| {
+		let entry = summary.entry(prefix(key)).or_default();
+		entry.read += removal.read;
+		entry.written += removal.written;
+	});
+
+	summary
+}
+
+/// Keys are grouped by their first 32 bytes, the pallet and storage prefix
+fn key_prefix(key: &[u8]) -> StorageKey {
+	key[0..core::cmp::min(32, key.len())].to_vec()
 }
 
-#[derive(thiserror::Error, Serialize, Deserialize, Encode, Decode, Copy, Clone, Debug)]
-pub enum Warning {
-	#[error("clear prefix without limit, cannot be tracked")]
-	ClearPrefixWithoutLimit,
-	#[error("child storage is not supported")]
-	ChildStorageNotSupported,
+/// Group keys by the longest known storage prefix. Well-known keys like
+/// `:transaction_level:` are kept as is.
+fn storage_prefix(known_prefixes: &[StorageKey], key: &[u8]) -> StorageKey {
+	known_prefixes
+		.iter()
+		.filter(|prefix| key.starts_with(prefix))
+		.max_by_key(|prefix| prefix.len())
+		.cloned()
+		.unwrap_or_else(|| {
+			if key.starts_with(b":") {
+				key.to_vec()
+			} else {
+				key_prefix(key)
+			}
+		})
 }
 
 pub struct BenchTracker {
+	known_prefixes: Vec<StorageKey>,
 	instant: RwLock<Instant>,
 	depth: RwLock<u32>,
 	redundant: RwLock<Instant>,
 	results: RwLock<Vec<u128>>,
 	main_keys: RwLock<HashMap<StorageKey, AccessInfo>>,
-	clear_prefixes: RwLock<HashMap<StorageKey, u32>>,
-	warnings: RwLock<Vec<Warning>>,
+	clear_prefixes: RwLock<HashMap<StorageKey, AccessReport>>,
+	child_keys: RwLock<HashMap<ChildKey, AccessInfo>>,
+	child_clear_prefixes: RwLock<HashMap<ChildKey, AccessReport>>,
 	whitelisted_keys: RwLock<HashMap<StorageKey, (bool, bool)>>,
+	whitelisted_prefixes: RwLock<Vec<(StorageKey, bool, bool)>>,
+	runs: RwLock<u32>,
+	/// Reads are not tracked nor traced while set, e.g. while warming the caches.
+	untracked: RwLock<bool>,
+	trace_run: Option<u32>,
+	trace: RwLock<Vec<TraceEntry>>,
+	panic: RwLock<Option<(String, u32)>>,
 }
 
 impl BenchTracker {
-	pub fn new() -> Self {
+	pub fn new(known_prefixes: Vec<StorageKey>) -> Self {
 		BenchTracker {
+			known_prefixes,
 			instant: RwLock::new(Instant::now()),
 			depth: RwLock::new(0),
 			redundant: RwLock::new(Instant::now()),
 			results: RwLock::new(Vec::new()),
 			main_keys: RwLock::new(HashMap::new()),
 			clear_prefixes: RwLock::new(HashMap::new()),
-			warnings: RwLock::new(Vec::new()),
+			child_keys: RwLock::new(HashMap::new()),
+			child_clear_prefixes: RwLock::new(HashMap::new()),
 			whitelisted_keys: RwLock::new(HashMap::new()),
+			whitelisted_prefixes: RwLock::new(Vec::new()),
+			runs: RwLock::new(0),
+			untracked: RwLock::new(false),
+			trace_run: None,
+			trace: RwLock::new(Vec::new()),
+			panic: RwLock::new(None),
 		}
 	}
 
-	pub fn warnings(&self) -> Vec<u8> {
-		let warnings = &*self.warnings.read();
-		warnings.encode()
+	/// Trace every storage access of `run`, counting warmup runs from 1
+	pub fn with_trace(mut self, run: u32) -> Self {
+		self.trace_run = Some(run);
+		self
+	}
+
+	fn is_traced(&self) -> bool {
+		self.trace_run == Some(*self.runs.read())
+	}
+
+	fn record_trace(
+		&self,
+		op: TraceOp,
+		child: StorageKey,
+		key: StorageKey,
+		access: AccessType,
+		size: u32,
+	) {
+		let whitelisted = access == AccessType::Whitelisted;
+		self.trace.write().push(TraceEntry {
+			op,
+			child,
+			key,
+			redundant: self.is_redundant(),
+			access,
+			depth: *self.depth.read(),
+			whitelisted,
+			size,
+		});
+	}
+
+	/// Storage accesses of the traced run
+	pub fn trace(&self) -> Vec<TraceEntry> {
+		self.trace.read().clone()
 	}
 
 	pub fn instant(&self) {
@@ -128,145 +346,245 @@ impl BenchTracker {
 		*self.depth.read() > 1
 	}
 
-	pub fn on_read_storage(&self, key: StorageKey) {
-		let redundant = self.is_redundant();
-		let main_keys = &mut *self.main_keys.write();
-		match main_keys.get_mut(&key) {
-			Some(info) => {
-				if redundant {
-					return;
-				}
-				if info.written.is_important() {
-					return;
-				}
-				info.read.mark_important();
-			},
-			None => {
-				main_keys.insert(key, AccessInfo::read(redundant));
-			},
-		};
+	/// Reads and writes whitelisted by the longest whitelisted prefix of `key`
+	fn whitelisted_prefix(&self, key: &[u8]) -> Option<(bool, bool)> {
+		self.whitelisted_prefixes
+			.read()
+			.iter()
+			.filter(|(prefix, ..)| key.starts_with(prefix))
+			.max_by_key(|(prefix, ..)| prefix.len())
+			.map(|(_, read, write)| (*read, *write))
 	}
 
-	pub fn on_read_child_storage(&self, _child_info: &ChildInfo, _key: StorageKey) {
-		if self.is_redundant() {
+	/// Keys under a whitelisted prefix are whitelisted on their first access
+	fn whitelist_by_prefix(&self, main_keys: &mut HashMap<StorageKey, AccessInfo>, key: &[u8]) {
+		if main_keys.contains_key(key) {
 			return;
 		}
-		self.warn(Warning::ChildStorageNotSupported);
+		if let Some((read, write)) = self.whitelisted_prefix(key) {
+			main_keys.insert(key.to_vec(), AccessInfo::whitelisted(read, write));
+		}
 	}
 
-	pub fn on_update_storage(&self, key: StorageKey) {
+	/// Run `f` without tracking the storage reads it makes
+	pub fn untracked<R>(&self, f: impl FnOnce() -> R) -> R {
+		*self.untracked.write() = true;
+		let ret = f();
+		*self.untracked.write() = false;
+		ret
+	}
+
+	/// `len` is the length of the value read, zero if only the key or hash is read
+	pub fn on_read_storage(&self, key: StorageKey, len: u32) {
+		if *self.untracked.read() {
+			return;
+		}
 		let redundant = self.is_redundant();
-		let main_keys = &mut *self.main_keys.write();
-		match main_keys.get_mut(&key) {
-			Some(info) => {
-				if redundant {
-					return;
-				}
-				info.written.mark_important();
-			},
-			None => {
-				main_keys.insert(key, AccessInfo::written(redundant));
-			},
-		};
+		let traced = self.is_traced().then(|| key.clone());
+		let main_keys = &mut self.main_keys.write();
+		self.whitelist_by_prefix(main_keys, &key);
+		track_read(main_keys, key, redundant, len);
+		if let Some(key) = traced {
+			let access = main_keys[&key].read;
+			self.record_trace(TraceOp::Read, vec![], key, access, len);
+		}
 	}
 
-	pub fn on_update_child_storage(&self, _child_info: &ChildInfo, _key: StorageKey) {
-		if self.is_redundant() {
+	pub fn on_read_child_storage(&self, child_info: &ChildInfo, key: StorageKey, len: u32) {
+		if *self.untracked.read() {
 			return;
 		}
-		self.warn(Warning::ChildStorageNotSupported);
+		let redundant = self.is_redundant();
+		let key = (child_info.storage_key().to_vec(), key);
+		let traced = self.is_traced().then(|| key.clone());
+		let child_keys = &mut self.child_keys.write();
+		track_read(child_keys, key, redundant, len);
+		if let Some(key) = traced {
+			let access = child_keys[&key].read;
+			self.record_trace(TraceOp::Read, key.0, key.1, access, len);
+		}
 	}
 
-	pub fn on_clear_prefix(&self, prefix: &[u8], limit: Option<u32>) {
-		if self.is_redundant() {
+	/// `len` is the length of the value written, zero for removals
+	pub fn on_update_storage(&self, key: StorageKey, len: u32) {
+		let redundant = self.is_redundant();
+		let traced = self.is_traced().then(|| key.clone());
+		let main_keys = &mut self.main_keys.write();
+		self.whitelist_by_prefix(main_keys, &key);
+		track_write(main_keys, key, redundant, len);
+		if let Some(key) = traced {
+			let access = main_keys[&key].written;
+			self.record_trace(TraceOp::Write, vec![], key, access, len);
+		}
+	}
+
+	/// `len` is the length of the appended item
+	pub fn on_append_storage(&self, key: StorageKey, len: u32) {
+		let redundant = self.is_redundant();
+		let traced = self.is_traced().then(|| key.clone());
+		let main_keys = &mut self.main_keys.write();
+		self.whitelist_by_prefix(main_keys, &key);
+		track_append(main_keys, key, redundant, len);
+		if let Some(key) = traced {
+			let access = main_keys[&key].written;
+			self.record_trace(TraceOp::Write, vec![], key, access, len);
+		}
+	}
+
+	pub fn on_update_child_storage(&self, child_info: &ChildInfo, key: StorageKey, len: u32) {
+		let redundant = self.is_redundant();
+		let key = (child_info.storage_key().to_vec(), key);
+		let traced = self.is_traced().then(|| key.clone());
+		let child_keys = &mut self.child_keys.write();
+		track_write(child_keys, key, redundant, len);
+		if let Some(key) = traced {
+			let access = child_keys[&key].written;
+			self.record_trace(TraceOp::Write, key.0, key.1, access, len);
+		}
+	}
+
+	fn trace_clear_prefix(&self, child: StorageKey, prefix: &[u8], removal: &MultiRemovalResults) {
+		if !self.is_traced() {
 			return;
 		}
-		if let Some(limit) = limit {
-			let key = prefix.to_vec();
-			let clear_prefixes = &mut *self.clear_prefixes.write();
-			match clear_prefixes.get_mut(&key) {
-				Some(n) => {
-					*n += limit;
-				},
-				None => {
-					clear_prefixes.insert(key, limit);
-				},
-			};
+		let access = if self.is_redundant() {
+			AccessType::Redundant
 		} else {
-			self.warn(Warning::ClearPrefixWithoutLimit);
+			AccessType::Important
+		};
+		self.record_trace(
+			TraceOp::ClearPrefix,
+			child,
+			prefix.to_vec(),
+			access,
+			removal.unique,
+		);
+	}
+
+	pub fn on_clear_prefix(&self, prefix: &[u8], removal: &MultiRemovalResults) {
+		self.trace_clear_prefix(vec![], prefix, removal);
+		if self.is_redundant() {
+			return;
 		}
+		let whitelisted = self.whitelisted_prefix(prefix).unwrap_or_default();
+		track_clear_prefix(
+			&mut self.clear_prefixes.write(),
+			prefix.to_vec(),
+			removal,
+			whitelisted,
+		);
 	}
 
 	pub fn on_clear_child_prefix(
 		&self,
-		_child_info: &ChildInfo,
-		_prefix: &[u8],
-		_limit: Option<u32>,
+		child_info: &ChildInfo,
+		prefix: &[u8],
+		removal: &MultiRemovalResults,
 	) {
+		self.trace_clear_prefix(child_info.storage_key().to_vec(), prefix, removal);
 		if self.is_redundant() {
 			return;
 		}
-		self.warn(Warning::ChildStorageNotSupported);
+		let key = (child_info.storage_key().to_vec(), prefix.to_vec());
+		track_clear_prefix(
+			&mut self.child_clear_prefixes.write(),
+			key,
+			removal,
+			(false, false),
+		);
 	}
 
-	pub fn on_kill_child_storage(&self, _child_info: &ChildInfo, _limit: Option<u32>) {
-		if self.is_redundant() {
-			return;
-		}
-		self.warn(Warning::ChildStorageNotSupported);
+	pub fn on_kill_child_storage(&self, child_info: &ChildInfo, removal: &MultiRemovalResults) {
+		self.on_clear_child_prefix(child_info, &[], removal);
+	}
+
+	/// Keys and child trie keys accessed since the last `prepare_next_run`
+	pub fn accessed_storage_keys(&self) -> (Vec<StorageKey>, Vec<ChildKey>) {
+		(
+			self.main_keys.read().keys().cloned().collect(),
+			self.child_keys.read().keys().cloned().collect(),
+		)
 	}
 
 	/// Get the benchmark summary
 	pub fn read_written_keys(&self) -> Vec<u8> {
-		let mut summary = HashMap::<StorageKey, AccessReport>::new();
-
-		self.main_keys.read().iter().for_each(|(key, info)| {
-			let prefix_end = core::cmp::min(32, key.len());
-			let prefix = key[0..prefix_end].to_vec();
-			if let Some(report) = summary.get_mut(&prefix) {
-				if info.read.is_important() {
-					report.read += 1;
-				}
-				if info.written.is_important() {
-					report.written += 1;
-				}
-			} else {
-				let mut report = AccessReport::default();
-				if info.read.is_important() {
-					report.read += 1;
-				}
-				if info.written.is_important() {
-					report.written += 1;
-				}
-				if report.read + report.written > 0 {
-					summary.insert(prefix, report);
-				}
-			}
-		});
+		summarize(&self.main_keys.read(), &self.clear_prefixes.read(), |key| {
+			storage_prefix(&self.known_prefixes, key)
+		})
+		.into_iter()
+		.map(|(prefix, report)| (prefix, report.read, report.written))
+		.collect::<Vec<(StorageKey, u32, u32)>>()
+		.encode()
+	}
 
-		self.clear_prefixes.read().iter().for_each(|(key, items)| {
-			let prefix_end = core::cmp::min(32, key.len());
-			let prefix = key[0..prefix_end].to_vec();
-			if let Some(report) = summary.get_mut(&prefix) {
-				report.written += items;
-			} else {
-				summary.insert(
-					prefix,
-					AccessReport {
-						written: *items,
-						..Default::default()
-					},
-				);
-			}
+	/// Get the total reads and writes of the main and child tries, encoded as
+	/// `(reads, writes)`
+	pub fn reads_and_writes(&self) -> Vec<u8> {
+		let main = summarize(&self.main_keys.read(), &self.clear_prefixes.read(), |key| {
+			key.clone()
 		});
+		let child = summarize(
+			&self.child_keys.read(),
+			&self.child_clear_prefixes.read(),
+			|key| key.clone(),
+		);
+		main.values()
+			.chain(child.values())
+			.fold((0u32, 0u32), |(reads, writes), report| {
+				(reads + report.read, writes + report.written)
+			})
+			.encode()
+	}
 
-		summary
+	/// Get bytes read and written per prefix. Child tries are listed by their
+	/// child storage key, like in `read_written_child_keys`
+	pub fn read_written_bytes(&self) -> Vec<u8> {
+		let mut bytes = summarize(&self.main_keys.read(), &self.clear_prefixes.read(), |key| {
+			storage_prefix(&self.known_prefixes, key)
+		})
+		.into_iter()
+		.map(|(prefix, report)| (prefix, report.bytes_read, report.bytes_written))
+		.collect::<Vec<(StorageKey, u32, u32)>>();
+
+		let child_keys = self.child_keys.read();
+		let child_clear_prefixes = self.child_clear_prefixes.read();
+		bytes.extend(
+			summarize(&child_keys, &child_clear_prefixes, |(child, _)| {
+				(child.clone(), vec![])
+			})
 			.into_iter()
-			.map(|(prefix, report)| (prefix, report.read, report.written))
+			.map(|((child, _), report)| (child, report.bytes_read, report.bytes_written)),
+		);
+
+		bytes.encode()
+	}
+
+	/// Get reads and writes of each accessed key, removals are listed by
+	/// their prefix
+	pub fn accessed_keys(&self) -> Vec<u8> {
+		let main_keys = self.main_keys.read();
+		let clear_prefixes = self.clear_prefixes.read();
+		summarize(&main_keys, &clear_prefixes, |key| key.clone())
+			.into_iter()
+			.map(|(key, report)| (key, report.read, report.written))
 			.collect::<Vec<(StorageKey, u32, u32)>>()
 			.encode()
 	}
 
+	/// Get the benchmark summary of child tries, per child storage key and
+	/// prefix in the child trie
+	pub fn read_written_child_keys(&self) -> Vec<u8> {
+		let child_keys = self.child_keys.read();
+		let child_clear_prefixes = self.child_clear_prefixes.read();
+		summarize(&child_keys, &child_clear_prefixes, |(child, key)| {
+			(child.clone(), key_prefix(key))
+		})
+		.into_iter()
+		.map(|((child, prefix), report)| (child, prefix, report.read, report.written))
+		.collect::<Vec<(StorageKey, StorageKey, u32, u32)>>()
+		.encode()
+	}
+
 	/// Run before executing the code been benchmarked
 	pub fn before_block(&self) {
 		let timestamp = Instant::now();
@@ -296,12 +614,6 @@ impl BenchTracker {
 		*depth -= 1;
 	}
 
-	/// Add a warning to be printed after execution
-	pub fn warn(&self, warning: Warning) {
-		let mut warnings = self.warnings.write();
-		warnings.push(warning);
-	}
-
 	/// Redundant elapsed time
 	pub fn redundant_time(&self) -> u128 {
 		assert_eq!(*self.depth.read(), 0, "benchmark in progress");
@@ -321,7 +633,8 @@ impl BenchTracker {
 		self.results.write().clear();
 
 		self.clear_prefixes.write().clear();
-		self.warnings.write().clear();
+		self.child_keys.write().clear();
+		self.child_clear_prefixes.write().clear();
 
 		let main_keys = &mut self.main_keys.write();
 		main_keys.clear();
@@ -332,13 +645,234 @@ impl BenchTracker {
 		}
 	}
 
+	/// Count a new run of the bench function, including warmup runs
+	pub fn start_run(&self) {
+		*self.runs.write() += 1;
+	}
+
+	/// Keep the panic message of the runtime together with the run it happened in
+	pub fn record_panic(&self, message: String) {
+		let run = *self.runs.read();
+		self.panic.write().get_or_insert((message, run));
+	}
+
+	/// Number of runs started so far
+	pub fn runs(&self) -> u32 {
+		*self.runs.read()
+	}
+
+	/// Panic message and run recorded by the runtime, if any
+	pub fn panic_info(&self) -> Option<(String, u32)> {
+		self.panic.read().clone()
+	}
+
 	/// Whitelist keys that don't need to be tracked
 	pub fn whitelist(&self, key: Vec<u8>, read: bool, write: bool) {
 		let whitelisted = &mut self.whitelisted_keys.write();
 		whitelisted.insert(key, (read, write));
 	}
+
+	/// Whitelist all keys under `prefix`, e.g. a whole storage map. Keys
+	/// whitelisted one by one take precedence.
+	pub fn whitelist_prefix(&self, prefix: Vec<u8>, read: bool, write: bool) {
+		let whitelisted = &mut self.whitelisted_prefixes.write();
+		whitelisted.retain(|(x, ..)| *x != prefix);
+		whitelisted.push((prefix, read, write));
+	}
 }
 
 sp_externalities::decl_extension! {
 	pub struct BenchTrackerExt(Arc<BenchTracker>);
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+
+	fn bytes(tracker: &BenchTracker) -> Vec<(StorageKey, u32, u32)> {
+		Decode::decode(&mut &tracker.read_written_bytes()[..]).unwrap()
+	}
+
+	fn reads_and_writes(tracker: &BenchTracker) -> (u32, u32) {
+		Decode::decode(&mut &tracker.reads_and_writes()[..]).unwrap()
+	}
+
+	/// Reads and writes per prefix, sorted by prefix
+	fn keys(tracker: &BenchTracker) -> Vec<(StorageKey, u32, u32)> {
+		let mut keys: Vec<(StorageKey, u32, u32)> =
+			Decode::decode(&mut &tracker.read_written_keys()[..]).unwrap();
+		keys.sort();
+		keys
+	}
+
+	#[test]
+	fn appends_add_up() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.on_append_storage(b":append".to_vec(), 4);
+		tracker.on_append_storage(b":append".to_vec(), 4);
+		tracker.on_append_storage(b":append".to_vec(), 2);
+		assert_eq!(bytes(&tracker), vec![(b":append".to_vec(), 0, 10)]);
+	}
+
+	#[test]
+	fn appends_add_to_written_value() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.on_update_storage(b":append".to_vec(), 8);
+		tracker.on_append_storage(b":append".to_vec(), 4);
+		assert_eq!(bytes(&tracker), vec![(b":append".to_vec(), 0, 12)]);
+
+		// a write replaces the value
+		tracker.on_update_storage(b":append".to_vec(), 3);
+		assert_eq!(bytes(&tracker), vec![(b":append".to_vec(), 0, 3)]);
+	}
+
+	#[test]
+	fn untracked_reads_are_skipped() {
+		let tracker = BenchTracker::new(vec![]).with_trace(0);
+		tracker.untracked(|| {
+			tracker.on_read_storage(b":warm".to_vec(), 4);
+			tracker.on_read_child_storage(&ChildInfo::new_default(b"child"), b":warm".to_vec(), 4);
+		});
+		assert_eq!(tracker.accessed_storage_keys(), (vec![], vec![]));
+		assert!(tracker.trace().is_empty());
+
+		tracker.on_read_storage(b":read".to_vec(), 4);
+		assert_eq!(bytes(&tracker), vec![(b":read".to_vec(), 4, 0)]);
+		assert_eq!(tracker.trace().len(), 1);
+	}
+
+	fn removal(loops: u32, unique: u32) -> MultiRemovalResults {
+		MultiRemovalResults {
+			maybe_cursor: None,
+			backend: unique,
+			unique,
+			loops,
+		}
+	}
+
+	#[test]
+	fn clear_prefix_counts_loops_and_removed_keys() {
+		let tracker = BenchTracker::new(vec![b"bar".to_vec(), b"baz".to_vec()]);
+		tracker.on_clear_prefix(b"bar", &removal(3, 2));
+		tracker.on_read_storage(b"baz1".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (4, 2));
+		assert_eq!(
+			keys(&tracker),
+			vec![(b"bar".to_vec(), 3, 2), (b"baz".to_vec(), 1, 0)]
+		);
+
+		// removals add up
+		tracker.on_clear_prefix(b"bar", &removal(1, 1));
+		assert_eq!(reads_and_writes(&tracker), (5, 3));
+
+		// removals in nested benchmarkable calls are redundant
+		tracker.before_block();
+		tracker.before_block();
+		tracker.on_clear_prefix(b"baz", &removal(5, 5));
+		tracker.after_block();
+		tracker.after_block();
+		assert_eq!(reads_and_writes(&tracker), (5, 3));
+	}
+
+	#[test]
+	fn clear_whitelisted_prefix() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.whitelist_prefix(b"bar".to_vec(), true, false);
+		tracker.whitelist_prefix(b"baz".to_vec(), true, true);
+		tracker.on_clear_prefix(b"bar", &removal(3, 2));
+		tracker.on_clear_prefix(b"baz", &removal(3, 2));
+		// removals under a longer prefix are whitelisted too
+		tracker.on_clear_prefix(b"bar1", &removal(1, 1));
+		assert_eq!(reads_and_writes(&tracker), (0, 3));
+		assert_eq!(
+			keys(&tracker),
+			vec![
+				(b"bar".to_vec(), 0, 2),
+				(b"bar1".to_vec(), 0, 1),
+				(b"baz".to_vec(), 0, 0)
+			]
+		);
+	}
+
+	#[test]
+	fn keys_under_whitelisted_prefix_are_not_counted() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.whitelist_prefix(b"bar".to_vec(), true, true);
+		tracker.prepare_next_run();
+		tracker.on_read_storage(b"bar1".to_vec(), 4);
+		tracker.on_update_storage(b"bar2".to_vec(), 4);
+		tracker.on_append_storage(b"bar3".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (0, 0));
+
+		tracker.on_read_storage(b"baz1".to_vec(), 4);
+		tracker.on_update_storage(b"baz1".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (1, 1));
+		assert_eq!(keys(&tracker), vec![(b"baz1".to_vec(), 1, 1)]);
+	}
+
+	#[test]
+	fn longest_whitelisted_prefix_wins() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.whitelist_prefix(b"bar".to_vec(), true, true);
+		tracker.whitelist_prefix(b"bar1".to_vec(), true, false);
+		tracker.prepare_next_run();
+		tracker.on_update_storage(b"bar2".to_vec(), 4);
+		tracker.on_update_storage(b"bar11".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (0, 1));
+		assert_eq!(keys(&tracker), vec![(b"bar11".to_vec(), 0, 1)]);
+
+		// whitelisting a prefix again replaces it
+		tracker.whitelist_prefix(b"bar1".to_vec(), true, true);
+		tracker.prepare_next_run();
+		tracker.on_update_storage(b"bar11".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (0, 0));
+	}
+
+	#[test]
+	fn whitelisted_keys_take_precedence_over_prefixes() {
+		let tracker = BenchTracker::new(vec![]);
+		tracker.whitelist_prefix(b"bar".to_vec(), true, true);
+		tracker.whitelist(b"bar1".to_vec(), true, false);
+		tracker.prepare_next_run();
+		tracker.on_read_storage(b"bar1".to_vec(), 4);
+		tracker.on_update_storage(b"bar1".to_vec(), 4);
+		tracker.on_update_storage(b"bar2".to_vec(), 4);
+		assert_eq!(reads_and_writes(&tracker), (0, 1));
+		assert_eq!(keys(&tracker), vec![(b"bar1".to_vec(), 0, 1)]);
+	}
+
+	#[test]
+	fn child_keys_are_counted_per_child_and_prefix() {
+		let tracker = BenchTracker::new(vec![]);
+		let (foo, bar) = (
+			ChildInfo::new_default(b"foo"),
+			ChildInfo::new_default(b"bar"),
+		);
+		// keys are grouped by their first 32 bytes
+		let key = |prefix: u8, i: u8| [vec![prefix; 32], vec![i]].concat();
+		tracker.on_read_child_storage(&foo, key(1, 1), 4);
+		tracker.on_read_child_storage(&foo, key(1, 2), 4);
+		tracker.on_update_child_storage(&foo, key(1, 2), 4);
+		tracker.on_update_child_storage(&foo, key(2, 1), 4);
+		tracker.on_read_child_storage(&bar, key(1, 1), 4);
+		tracker.on_kill_child_storage(&bar, &removal(3, 2));
+		tracker.on_read_storage(b"baz".to_vec(), 4);
+
+		let mut child_keys: Vec<(StorageKey, StorageKey, u32, u32)> =
+			Decode::decode(&mut &tracker.read_written_child_keys()[..]).unwrap();
+		child_keys.sort();
+		assert_eq!(
+			child_keys,
+			vec![
+				(b"bar".to_vec(), vec![], 3, 2),
+				(b"bar".to_vec(), vec![1; 32], 1, 0),
+				(b"foo".to_vec(), vec![1; 32], 2, 1),
+				(b"foo".to_vec(), vec![2; 32], 0, 1),
+			]
+		);
+		assert_eq!(reads_and_writes(&tracker), (2 + 1 + 3 + 1, 1 + 1 + 2));
+		// child keys are not listed with the main trie keys
+		assert_eq!(keys(&tracker), vec![(b"baz".to_vec(), 1, 0)]);
+	}
+}
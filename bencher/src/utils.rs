@@ -1,24 +1,46 @@
 use sp_std::vec::Vec;
 
 #[cfg(feature = "std")]
-use super::colorize::red_bold;
-#[cfg(feature = "std")]
-use super::tracker::BenchTrackerExt;
+use super::{bench_runner::ProofRecorderExt, tracker::BenchTrackerExt};
 #[cfg(feature = "std")]
 use sp_externalities::ExternalitiesExt;
+#[cfg(feature = "std")]
+use sp_std::sync::Arc;
+#[cfg(feature = "std")]
+use sp_storage::ChildInfo;
+
+/// Marks a call of a benchmarkable method, calls nested in it are redundant.
+/// The call ends when the guard is dropped, also on early return or `?`.
+#[doc(hidden)]
+pub struct BlockGuard;
+
+impl BlockGuard {
+	pub fn enter() -> Self {
+		bench::before_block();
+		BlockGuard
+	}
+}
+
+impl Drop for BlockGuard {
+	fn drop(&mut self) {
+		bench::after_block();
+	}
+}
 
 #[sp_runtime_interface::runtime_interface]
 pub trait Bench {
-	fn print_error(message: Vec<u8>) {
-		let msg = String::from_utf8_lossy(&message);
-		eprintln!("{}", red_bold(&msg));
+	fn record_panic(&mut self, message: Vec<u8>) {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.record_panic(String::from_utf8_lossy(&message).to_string());
 	}
 
-	fn warnings(&mut self) -> Vec<u8> {
+	fn start_run(&mut self) {
 		let tracker = &***self
 			.extension::<BenchTrackerExt>()
 			.expect("No `bench_tracker` associated for the current context!");
-		tracker.warnings()
+		tracker.start_run();
 	}
 
 	fn commit_db(&mut self) {
@@ -29,10 +51,37 @@ pub trait Bench {
 		self.wipe()
 	}
 
+	/// Read the keys accessed since the last measurement, filling the trie cache.
+	/// These reads are not tracked and the proof they recorded is discarded,
+	/// so the measured code records the same proof as with cold caches.
+	fn warm_storage(&mut self) {
+		let tracker = Arc::clone(
+			&**self
+				.extension::<BenchTrackerExt>()
+				.expect("No `bench_tracker` associated for the current context!"),
+		);
+		let (keys, child_keys) = tracker.accessed_storage_keys();
+		tracker.untracked(|| {
+			for key in keys {
+				self.storage(&key);
+			}
+			for (child, key) in child_keys {
+				self.child_storage(&ChildInfo::new_default(&child), &key);
+			}
+		});
+		if let Some(recorder) = self.extension::<ProofRecorderExt>() {
+			recorder.reset();
+		}
+	}
+
 	fn reset_read_write_count(&mut self) {
 		self.reset_read_write_count()
 	}
 
+	fn proof_size(&mut self) -> u32 {
+		self.proof_size().unwrap_or_default()
+	}
+
 	fn start_timer(&mut self) {
 		let tracker = &***self
 			.extension::<BenchTrackerExt>()
@@ -76,10 +125,45 @@ pub trait Bench {
 		tracker.read_written_keys()
 	}
 
+	fn reads_and_writes(&mut self) -> Vec<u8> {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.reads_and_writes()
+	}
+
+	fn read_written_bytes(&mut self) -> Vec<u8> {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.read_written_bytes()
+	}
+
+	fn accessed_keys(&mut self) -> Vec<u8> {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.accessed_keys()
+	}
+
+	fn read_written_child_keys(&mut self) -> Vec<u8> {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.read_written_child_keys()
+	}
+
 	fn whitelist(&mut self, key: Vec<u8>, read: bool, write: bool) {
 		let tracker = &***self
 			.extension::<BenchTrackerExt>()
 			.expect("No `bench_tracker` associated for the current context!");
 		tracker.whitelist(key, read, write);
 	}
+
+	fn whitelist_prefix(&mut self, prefix: Vec<u8>, read: bool, write: bool) {
+		let tracker = &***self
+			.extension::<BenchTrackerExt>()
+			.expect("No `bench_tracker` associated for the current context!");
+		tracker.whitelist_prefix(prefix, read, write);
+	}
 }
@@ -1 +1,19 @@
-wasm_bencher::main!({ wasm_bencher_test::mock::AllPalletsWithSystem::storage_info() });
+use codec::Encode;
+use frame_support::sp_runtime::BuildStorage;
+use frame_support::storage::StoragePrefixedMap;
+use frame_support::traits::WhitelistedStorageKeys;
+use wasm_bencher_test::{
+	mock::{AllPalletsWithSystem, Runtime},
+	pallet::Foo,
+};
+
+wasm_bencher::main!(
+	{ wasm_bencher_test::mock::AllPalletsWithSystem::storage_info() },
+	genesis: {
+		let mut genesis = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+		genesis.top.insert(Foo::<Runtime>::hashed_key().to_vec(), 7u32.encode());
+		genesis
+	},
+	whitelist: { AllPalletsWithSystem::whitelisted_storage_keys() },
+	whitelist_prefixes: { vec![frame_system::Account::<Runtime>::final_prefix().to_vec()] }
+);
@@ -2,8 +2,8 @@
 #![allow(dead_code)]
 
 use crate::{mock::*, pallet::*};
-use frame_support::assert_ok;
-use wasm_bencher::{benches, Bencher};
+use frame_support::{assert_ok, storage::StoragePrefixedMap, traits::WhitelistedStorageKeys};
+use wasm_bencher::{benches, Bencher, StorageMode};
 
 fn set_value(b: &mut Bencher) {
 	let result = b.bench(|| Test::set_value(RuntimeOrigin::signed(1), 1));
@@ -11,14 +11,81 @@ fn set_value(b: &mut Bencher) {
 	assert_eq!(Test::value(), Some(1 + 1));
 }
 
+fn set_value_warm(b: &mut Bencher) {
+	let result = b
+		.storage_mode(StorageMode::Warm)
+		.bench(|| Test::set_value(RuntimeOrigin::signed(1), 1));
+	assert_ok!(result);
+}
+
+fn warm_proof_size_matches_cold(b: &mut Bencher) {
+	// reads only, both measurements see the same state
+	b.storage_mode(StorageMode::Cold).bench_named("read_foo_cold", Test::foo);
+	b.storage_mode(StorageMode::Warm).bench_named("read_foo_warm", Test::foo);
+	// caching doesn't change what goes into the proof
+	#[cfg(not(feature = "std"))]
+	{
+		let cold = b.measurements[0].proof_sizes.last();
+		assert!(cold > Some(&0));
+		assert_eq!(cold, b.measurements[1].proof_sizes.last());
+	}
+}
+
 fn set_foo(b: &mut Bencher) {
 	b.bench(|| {
 		let _ = Test::set_foo();
 	});
 }
 
-fn clear_prefix_warning(b: &mut Bencher) {
+fn set_foo_renamed(b: &mut Bencher) {
+	b.name("set_foo_again").bench(|| {
+		let _ = Test::set_foo();
+	});
+}
+
+fn set_foo_and_value(b: &mut Bencher) {
+	b.bench_named("set_foo_only", || {
+		let _ = Test::set_foo();
+	});
+	b.bench_named("set_value_only", || {
+		let _ = Test::set_value(RuntimeOrigin::signed(1), 1);
+	});
+}
+
+fn set_foo_after_error(b: &mut Bencher) {
+	b.bench(|| {
+		assert!(Test::fail_without_value().is_err());
+		let _ = Test::set_foo();
+	});
+	// the failed call must not leave `set_foo` nested in it, making its writes redundant
+	#[cfg(not(feature = "std"))]
+	{
+		let keys =
+			<Vec<(Vec<u8>, u32, u32)> as codec::Decode>::decode(&mut &b.measurements[0].keys[..])
+				.unwrap();
+		let foo = Foo::<Runtime>::hashed_key().to_vec();
+		assert!(keys.iter().any(|(prefix, _, writes)| *prefix == foo && *writes > 0));
+	}
+}
+
+fn read_genesis_foo(b: &mut Bencher) {
+	let foo = b.bench(Test::foo);
+	// set by the genesis in `benches/foo.rs`, native tests start from empty storage
+	if cfg!(not(feature = "std")) {
+		assert_eq!(foo, Some(7));
+	}
+}
+
+fn set_bars(b: &mut Bencher) {
+	let n = b.component("n", 1..=100);
+	b.bench(|| {
+		Test::set_bars(n);
+	});
+}
+
+fn clear_prefix_without_limit(b: &mut Bencher) {
 	Bar::<Runtime>::insert(1, 1);
+	Bar::<Runtime>::insert(2, 2);
 	b.bench(|| {
 		Test::clear_prefix_bar();
 	});
@@ -31,6 +98,42 @@ fn clear_prefix_with_limit(b: &mut Bencher) {
 	});
 }
 
+fn set_bars_with_whitelisted_prefix(b: &mut Bencher) {
+	b.whitelist_prefix(Bar::<Runtime>::final_prefix().to_vec(), true, true);
+	b.bench(|| {
+		Test::set_bars(10);
+	});
+}
+
+fn set_children(b: &mut Bencher) {
+	b.bench(|| {
+		Test::set_children(10);
+	});
+}
+
+fn read_whitelisted_keys(b: &mut Bencher) {
+	// `frame_system::Number` is whitelisted for all benches below
+	b.bench_named("read_block_number", System::block_number);
+	// `frame_system::BlockHash` is whitelisted by prefix for all benches below
+	b.bench_named("read_block_hash", || {
+		frame_system::BlockHash::<Runtime>::get(0)
+	});
+	// `frame_system::Account` is whitelisted by prefix in `main!`
+	b.bench_named("read_account", || frame_system::Account::<Runtime>::get(1));
+	b.bench_named("read_value", Test::value);
+	#[cfg(not(feature = "std"))]
+	{
+		let counts = |i: usize| {
+			let measurement = &b.measurements[i];
+			(measurement.reads.last(), measurement.writes.last())
+		};
+		for i in 0..3 {
+			assert_eq!(counts(i), (Some(&0), Some(&0)));
+		}
+		assert_eq!(counts(3), (Some(&1), Some(&0)));
+	}
+}
+
 fn set_foo_with_whitelist(b: &mut Bencher) {
 	b.whitelist(Bar::<Runtime>::hashed_key_for(1), true, true);
 	b.whitelist(Bar::<Runtime>::hashed_key_for(2), true, false);
@@ -44,7 +147,19 @@ fn set_foo_with_whitelist(b: &mut Bencher) {
 benches!(
 	set_foo_with_whitelist,
 	set_value,
+	set_value_warm,
+	warm_proof_size_matches_cold,
 	set_foo,
-	clear_prefix_warning,
-	clear_prefix_with_limit
+	set_foo_renamed,
+	set_foo_and_value,
+	set_foo_after_error,
+	read_genesis_foo,
+	set_bars = { iterations: 200 },
+	clear_prefix_without_limit,
+	clear_prefix_with_limit,
+	set_bars_with_whitelisted_prefix,
+	set_children,
+	read_whitelisted_keys;
+	whitelist: { AllPalletsWithSystem::whitelisted_storage_keys() },
+	whitelist_prefixes: { sp_std::vec![frame_system::BlockHash::<Runtime>::final_prefix().to_vec()] }
 );
@@ -13,6 +13,7 @@ pub mod pallet {
 	use frame_support::{
 		dispatch::{DispatchResult, DispatchResultWithPostInfo},
 		pallet_prelude::*,
+		storage::child,
 		StoragePrefixedMap,
 	};
 	use frame_system::pallet_prelude::*;
@@ -42,14 +43,14 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 		#[pallet::call_index(0)]
 		#[pallet::weight({0})]
-		#[weight_meter::start(ModuleWeights::<T>::set_value().ref_time())]
+		#[weight_meter::start(ModuleWeights::<T>::set_value())]
 		pub fn set_value(origin: OriginFor<T>, n: u32) -> DispatchResultWithPostInfo {
 			frame_system::ensure_signed(origin)?;
 			Value::<T>::get();
 			Value::<T>::put(n);
 			Value::<T>::put(n + 1);
 			let _ = Self::set_foo();
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(1)]
@@ -61,8 +62,8 @@ pub mod pallet {
 		}
 	}
 
+	#[weight_meter::weight]
 	impl<T: Config> Pallet<T> {
-		#[weight_meter::weight(ModuleWeights::<T>::set_foo().ref_time())]
 		pub(crate) fn set_foo() -> frame_support::dispatch::DispatchResult {
 			Value::<T>::put(2);
 
@@ -79,15 +80,37 @@ pub mod pallet {
 			Ok(())
 		}
 
-		#[weight_meter::weight(0)]
+		#[weight_meter::weight(Weight::zero())]
+		pub(crate) fn fail_without_value() -> DispatchResult {
+			Value::<T>::get().ok_or(DispatchError::Other("no value"))?;
+			Ok(())
+		}
+
+		#[weight_meter::weight(Weight::zero())]
+		pub(crate) fn set_bars(n: u32) {
+			for i in 0..n {
+				Bar::<T>::insert(i, i);
+			}
+		}
+
+		#[weight_meter::weight(Weight::zero())]
 		pub(crate) fn clear_prefix_bar() {
-			// This will cause warning because clear_prefix without limit cannot be counted.
+			// Removed keys are counted from the removal results, even without a limit.
 			let _ = storage::unhashed::clear_prefix(&Bar::<T>::final_prefix(), None, None);
 		}
 
-		#[weight_meter::weight(0)]
+		#[weight_meter::weight(Weight::zero())]
 		pub(crate) fn clear_bar_with_limit() {
 			_ = Bar::<T>::clear(10, None);
 		}
+
+		#[weight_meter::weight(Weight::zero())]
+		pub(crate) fn set_children(n: u32) {
+			let child_info = child::ChildInfo::new_default(b"children");
+			for i in 0..n {
+				child::put(&child_info, &i.encode(), &i);
+			}
+			let _ = child::get::<u32>(&child_info, &n.encode());
+		}
 	}
 }
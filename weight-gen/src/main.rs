@@ -2,10 +2,28 @@ use clap::{Arg, Command};
 use serde::{Deserialize, Serialize};
 use std::io::Read;
 
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct ComponentData {
+	pub name: String,
+	pub weight: u64,
+	#[serde(default)]
+	pub proof_size: u64,
+	#[serde(default)]
+	pub reads: u32,
+	#[serde(default)]
+	pub writes: u32,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct BenchData {
 	pub name: String,
 	pub weight: u64,
+	#[serde(default)]
+	pub proof_size: u64,
+	#[serde(default)]
+	pub estimated_proof_size: u64,
+	#[serde(default)]
+	pub components: Vec<ComponentData>,
 	pub reads: u32,
 	pub writes: u32,
 	pub comments: Vec<String>,
@@ -2,27 +2,51 @@
 
 //! 1. Add macro attribute on method you want to benchmark.
 //! ```ignore
-//! #[weight_meter::weight(0)]
+//! #[weight_meter::weight(Weight::zero())]
 //! fn inner_do_something(something: u32) {
 //!     // Update storage.
 //!     Something::<T>::put(something);
 //! }
 //! ```
-//! Start with `0` and after the weights is generated then it can be replaced
-//! with generated weight. Macro will inject callable methods that wraps inner
-//! methods. This only works for methods with `weight_meter::start` and
+//! Start with `Weight::zero()` and after the weights is generated then it can
+//! be replaced with generated weight. Macro will inject callable methods that
+//! wraps inner methods. This only works for methods with `weight_meter::start` and
 //! `weight_meter::weight` attribute, and only when running benchmarks.
 //!
 //! 2. Create benchmarks using wasm_bencher and generate the weights with weight_gen
 //!    After running the benchmarks and the weights have been generated then we can
 //!    replace
 //! ```ignore
-//! #[weight_meter::weight(0)]
+//! #[weight_meter::weight(Weight::zero())]
 //! ```
 //! with
 //!```ignore
 //! #[weight_meter::weight(ModuleWeights::<T>::inner_do_something())]
 //! ```
+//! Instead of adding the attribute to each method, `#[weight_meter::weight]`
+//! can be added to the impl block. Each method then uses the generated weight
+//! of the same name, `ModuleWeights::<T>::method_name(args..)`, or of another
+//! type with `#[weight_meter::weight(T::WeightInfo)]`. `T` is the first type
+//! parameter of the impl block. The arguments of the method are forwarded to
+//! the weight, which takes the components of the bench, so methods taking
+//! anything else than the components need their own weight. Methods with their
+//! own `#[weight_meter::weight(...)]` keep it and methods with
+//! `#[weight_meter::skip]` are not metered.
+//! ```ignore
+//! #[weight_meter::weight]
+//! impl<T: Config> Pallet<T> {
+//!     // uses `ModuleWeights::<T>::inner_do_something(n)`
+//!     fn inner_do_something(n: u32) {
+//!         Something::<T>::put(n);
+//!     }
+//!
+//!     #[weight_meter::weight(ModuleWeights::<T>::transfer())]
+//!     fn transfer(who: T::AccountId) {}
+//!
+//!     #[weight_meter::skip]
+//!     fn helper() {}
+//! }
+//! ```
 //!
 //! 3. Use WeightMeter on your calls by adding macro
 //!    `#[weight_meter::start(weight)]` or `#[weight_meter::start]` if
@@ -43,7 +67,7 @@
 //!    }
 //! ```
 
-type Weight = u64;
+pub use frame_support::weights::Weight;
 
 struct Meter {
 	used_weight: Weight,
@@ -52,6 +76,30 @@ struct Meter {
 	depth: u8,
 }
 
+impl Meter {
+	const fn new() -> Self {
+		Meter {
+			used_weight: Weight::zero(),
+			depth: 0,
+		}
+	}
+
+	fn start(&mut self, weight: Weight) {
+		if self.depth == 0 {
+			self.used_weight = weight;
+		}
+		self.depth = self.depth.saturating_add(1);
+	}
+
+	fn using(&mut self, weight: Weight) {
+		self.used_weight = self.used_weight.saturating_add(weight);
+	}
+
+	fn finish(&mut self) {
+		self.depth = self.depth.saturating_sub(1);
+	}
+}
+
 mod meter_no_std;
 mod meter_std;
 
@@ -69,8 +117,29 @@ pub use meter_std::*;
 #[cfg(not(feature = "std"))]
 pub use meter_no_std::*;
 
+/// Starts the weight meter and finishes it when dropped, also on early return
+/// or `?`. Used by `#[start]` within `metered`.
+#[doc(hidden)]
+pub struct MeterGuard;
+
+impl MeterGuard {
+	pub fn start(weight: Weight) -> Self {
+		start(weight);
+		MeterGuard
+	}
+}
+
+impl Drop for MeterGuard {
+	fn drop(&mut self) {
+		finish();
+	}
+}
+
 /// Start weight meter
 pub use weight_meter_procedural::start;
 
 /// Measure each methods weight
 pub use weight_meter_procedural::weight;
+
+/// Opt a method out of `#[weight]` on its impl block
+pub use weight_meter_procedural::skip;
@@ -1,40 +1,35 @@
-// TODO: research if there's a better way
-#![cfg(not(feature = "std"))]
+// Also built for tests, which run it against the std build of `environmental`
+#![cfg(any(not(feature = "std"), test))]
 
 use super::{Meter, Weight};
 
-static mut METER: Meter = Meter {
-	used_weight: 0,
-	depth: 0,
-};
+// Set by `metered` for the outermost `#[start]` call. The runtime is single
+// threaded, `environmental` keeps it in a thread local when built with std.
+environmental::environmental!(meter: Meter);
 
+/// Run `f` with the meter of the calling `#[start]` method, or with a new one.
+/// The meter only exists while `f` runs, it is not used outside of it.
+#[doc(hidden)]
+pub fn metered<R>(f: impl FnOnce() -> R) -> R {
+	meter::using_once(&mut Meter::new(), f)
+}
+
+/// Start weight meter with base weight
 pub fn start(weight: Weight) {
-	unsafe {
-		if METER.depth == 0 {
-			METER.used_weight = weight;
-		}
-		METER.depth = METER.depth.saturating_add(1);
-	}
+	meter::with(|meter| meter.start(weight));
 }
 
+/// Increment used weight
 pub fn using(weight: Weight) {
-	unsafe {
-		METER.used_weight = METER.used_weight.saturating_add(weight);
-	}
+	meter::with(|meter| meter.using(weight));
 }
 
+/// Finish weight meter
 pub fn finish() {
-	unsafe {
-		METER.depth.checked_sub(1).map_or_else(
-			|| {
-				debug_assert!(false);
-				0
-			},
-			|v| v,
-		);
-	}
+	meter::with(|meter| meter.finish());
 }
 
+/// Get used weight
 pub fn used_weight() -> Weight {
-	unsafe { METER.used_weight }
+	meter::with(|meter| meter.used_weight).unwrap_or_default()
 }
@@ -1,43 +1,31 @@
-// TODO: research if there's a better way
 #![cfg(feature = "std")]
 
 use super::{Meter, Weight};
 use std::cell::RefCell;
 
 thread_local! {
-	static METER: RefCell<Meter> = const {
-		RefCell::new(Meter {
-			used_weight: 0,
-			depth: 0,
-		})
-	};
+	static METER: RefCell<Meter> = const { RefCell::new(Meter::new()) };
+}
+
+/// Run `f`, the meter is kept in a thread local for the whole thread.
+#[doc(hidden)]
+pub fn metered<R>(f: impl FnOnce() -> R) -> R {
+	f()
 }
 
 /// Start weight meter with base weight
 pub fn start(weight: Weight) {
-	METER.with(|v| {
-		let mut meter = v.borrow_mut();
-		if meter.depth == 0 {
-			meter.used_weight = weight;
-		}
-		meter.depth = meter.depth.saturating_add(1);
-	});
+	METER.with(|v| v.borrow_mut().start(weight));
 }
 
 /// Increment used weight
 pub fn using(weight: Weight) {
-	METER.with(|v| {
-		let mut meter = v.borrow_mut();
-		meter.used_weight = meter.used_weight.saturating_add(weight);
-	})
+	METER.with(|v| v.borrow_mut().using(weight));
 }
 
 /// Finish weight meter
 pub fn finish() {
-	METER.with(|v| {
-		let mut meter = v.borrow_mut();
-		meter.depth = meter.depth.saturating_sub(1);
-	})
+	METER.with(|v| v.borrow_mut().finish());
 }
 
 /// Get used weight
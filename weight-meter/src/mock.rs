@@ -28,7 +28,7 @@ pub mod test_module {
 
 			Self::put_100();
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(1)]
@@ -43,7 +43,7 @@ pub mod test_module {
 			Self::put_100();
 			Self::put_100();
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(2)]
@@ -55,7 +55,7 @@ pub mod test_module {
 			Self::max_weight();
 			Self::put_100();
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(3)]
@@ -70,7 +70,7 @@ pub mod test_module {
 				Self::put_100();
 			}
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(4)]
@@ -81,7 +81,7 @@ pub mod test_module {
 
 			Self::put_300_nested();
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 
 		#[pallet::call_index(5)]
@@ -99,12 +99,31 @@ pub mod test_module {
 			// some module call
 			Self::put_300_nested();
 
-			Ok(Some(Weight::from_parts(weight_meter::used_weight(), 0)).into())
+			Ok(Some(weight_meter::used_weight()).into())
 		}
 	}
 
 	impl<T: Config> Pallet<T> {
-		#[weight_meter::weight(100)]
+		// Not dispatchable, `#[pallet::call]` runs the body of calls in a closure
+		#[weight_meter::start]
+		pub fn expect_100_or_200_early_return(early: bool) -> Weight {
+			Self::put_100();
+			if early {
+				return weight_meter::used_weight();
+			}
+			Self::put_100();
+
+			weight_meter::used_weight()
+		}
+
+		#[weight_meter::start]
+		pub fn expect_error(fail: bool) -> Result<Weight, DispatchError> {
+			Self::try_put_100(fail)?;
+
+			Ok(weight_meter::used_weight())
+		}
+
+		#[weight_meter::weight(Weight::from_parts(100, 10))]
 		fn put_100() {
 			let something = Self::something();
 
@@ -115,7 +134,7 @@ pub mod test_module {
 			}
 		}
 
-		#[weight_meter::weight(200)]
+		#[weight_meter::weight(Weight::from_parts(200, 20))]
 		fn put_200() {
 			let something = Self::something();
 
@@ -126,14 +145,50 @@ pub mod test_module {
 			}
 		}
 
-		#[weight_meter::weight(200)]
+		#[weight_meter::weight(Weight::from_parts(200, 20))]
 		fn put_300_nested() {
 			Self::put_100();
 		}
 
-		#[weight_meter::weight(Weight::MAX.ref_time())]
+		#[weight_meter::weight(Weight::from_parts(100, 10))]
+		fn try_put_100(fail: bool) -> DispatchResult {
+			ensure!(!fail, DispatchError::Other("failed"));
+			Self::put_100();
+			Ok(())
+		}
+
+		#[weight_meter::weight(Weight::MAX)]
 		fn max_weight() {}
 	}
+
+	pub struct ModuleWeights<T>(PhantomData<T>);
+	impl<T: Config> ModuleWeights<T> {
+		pub fn put_400() -> Weight {
+			Weight::from_parts(400, 40)
+		}
+	}
+
+	#[weight_meter::weight]
+	impl<T: Config> Pallet<T> {
+		#[weight_meter::start]
+		pub fn expect_550() -> Weight {
+			Self::put_400();
+			Self::put_50();
+			Self::put_100_skipped();
+
+			weight_meter::used_weight()
+		}
+
+		fn put_400() {}
+
+		#[weight_meter::weight(Weight::from_parts(50, 5))]
+		fn put_50() {}
+
+		#[weight_meter::skip]
+		fn put_100_skipped() {
+			Self::put_100();
+		}
+	}
 }
 
 use frame_support::{derive_impl, traits::ConstU128};
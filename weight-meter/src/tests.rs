@@ -6,12 +6,12 @@ fn used_weight_works() {
 	new_test_ext().execute_with(|| {
 		let result: PostDispatchInfo = TestModule::expect_100(RuntimeOrigin::signed(100)).unwrap();
 		// Check used weight is correct
-		assert_eq!(Some(Weight::from_parts(100, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::from_parts(100, 10)), result.actual_weight);
 		// Check that the method ran correctly
 		assert_eq!(Some(100), TestModule::something());
 
 		let result: PostDispatchInfo = TestModule::expect_500(RuntimeOrigin::signed(100)).unwrap();
-		assert_eq!(Some(Weight::from_parts(500, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::from_parts(500, 50)), result.actual_weight);
 		assert_eq!(Some(600), TestModule::something());
 	});
 }
@@ -22,14 +22,14 @@ fn used_weight_branch_works() {
 		let result: PostDispatchInfo =
 			TestModule::expect_100_or_200(RuntimeOrigin::signed(100), false).unwrap();
 		// Check used weight is correct
-		assert_eq!(Some(Weight::from_parts(100, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::from_parts(100, 10)), result.actual_weight);
 		// Check that the method ran correctly
 		assert_eq!(Some(100), TestModule::something());
 
 		let result: PostDispatchInfo =
 			TestModule::expect_100_or_200(RuntimeOrigin::signed(100), true).unwrap();
 		// Check used weight is correct
-		assert_eq!(Some(Weight::from_parts(200, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::from_parts(200, 20)), result.actual_weight);
 		// Check that the method ran correctly
 		assert_eq!(Some(300), TestModule::something());
 	});
@@ -41,7 +41,7 @@ fn used_weight_nested_calls_works() {
 		let result: PostDispatchInfo =
 			TestModule::nested_inner_methods(RuntimeOrigin::signed(100)).unwrap();
 		// Check used weight is correct
-		assert_eq!(Some(Weight::from_parts(300, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::from_parts(300, 30)), result.actual_weight);
 	});
 }
 
@@ -51,7 +51,7 @@ fn exceed_max_weight_works() {
 		let result: PostDispatchInfo =
 			TestModule::expect_max_weight(RuntimeOrigin::signed(100)).unwrap();
 		// Check used weight is correct
-		assert_eq!(Some(Weight::from_parts(u64::MAX, 0)), result.actual_weight);
+		assert_eq!(Some(Weight::MAX), result.actual_weight);
 	});
 }
 
@@ -59,6 +59,105 @@ fn exceed_max_weight_works() {
 fn nested_module_calls_works() {
 	new_test_ext().execute_with(|| {
 		let result = TestModule::nested_extrinsic(RuntimeOrigin::signed(0)).unwrap();
-		assert_eq!(result.actual_weight, Some(Weight::from_parts(700, 0)));
+		assert_eq!(result.actual_weight, Some(Weight::from_parts(700, 70)));
 	});
 }
+
+#[test]
+fn early_return_finishes_meter() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			TestModule::expect_100_or_200_early_return(true),
+			Weight::from_parts(100, 10)
+		);
+
+		// the meter starts again from zero
+		assert_eq!(
+			TestModule::expect_100_or_200_early_return(false),
+			Weight::from_parts(200, 20)
+		);
+	});
+}
+
+#[test]
+fn error_finishes_meter() {
+	new_test_ext().execute_with(|| {
+		assert!(TestModule::expect_error(true).is_err());
+
+		// the meter starts again from zero
+		assert_eq!(
+			TestModule::expect_error(false),
+			Ok(Weight::from_parts(200, 20))
+		);
+	});
+}
+
+#[test]
+fn impl_weights_works() {
+	new_test_ext().execute_with(|| {
+		// 400 from `ModuleWeights`, 50 from the override and 100 from the method
+		// called by the skipped one
+		assert_eq!(TestModule::expect_550(), Weight::from_parts(550, 55));
+	});
+}
+
+/// Checks a meter backend, `meter_std` or `meter_no_std`
+macro_rules! check_meter {
+	($meter:path) => {{
+		use $meter::*;
+
+		start(Weight::from_parts(100, 10));
+		using(Weight::from_parts(200, 20));
+		// nested start doesn't reset the used weight
+		start(Weight::from_parts(1_000, 100));
+		using(Weight::from_parts(300, 30));
+		finish();
+		assert_eq!(used_weight(), Weight::from_parts(600, 60));
+		finish();
+
+		// the meter starts again from the given weight
+		start(Weight::from_parts(100, 10));
+		assert_eq!(used_weight(), Weight::from_parts(100, 10));
+		using(Weight::MAX);
+		assert_eq!(used_weight(), Weight::MAX);
+		finish();
+
+		// finishing more than started doesn't underflow
+		finish();
+		start(Weight::zero());
+		assert_eq!(used_weight(), Weight::zero());
+		finish();
+	}};
+}
+
+#[test]
+fn meter_std_works() {
+	check_meter!(crate::meter_std);
+}
+
+#[test]
+fn meter_no_std_works() {
+	use crate::meter_no_std::*;
+
+	metered(|| check_meter!(crate::meter_no_std));
+
+	// the meter only exists within `metered`
+	start(Weight::from_parts(100, 10));
+	assert_eq!(used_weight(), Weight::zero());
+	finish();
+
+	// nested calls use the meter of the outermost one
+	let used = metered(|| {
+		start(Weight::from_parts(100, 10));
+		metered(|| {
+			start(Weight::from_parts(1_000, 100));
+			using(Weight::from_parts(200, 20));
+			finish();
+		});
+		assert_eq!(used_weight(), Weight::from_parts(300, 30));
+		finish();
+		used_weight()
+	});
+	assert_eq!(used, Weight::from_parts(300, 30));
+	assert_eq!(used_weight(), Weight::zero());
+}
@@ -1,43 +1,83 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse, Expr, ItemFn};
+use syn::{
+	parse2, parse_quote, spanned::Spanned, Attribute, Error, Expr, FnArg, ImplItem, Item, ItemFn,
+	ItemImpl, Pat, Result, ReturnType, Type,
+};
 
 #[proc_macro_attribute]
 pub fn start(attr: TokenStream, item: TokenStream) -> TokenStream {
+	expand_start(attr.into(), item.into())
+		.unwrap_or_else(Error::into_compile_error)
+		.into()
+}
+
+#[proc_macro_attribute]
+pub fn weight(attr: TokenStream, item: TokenStream) -> TokenStream {
+	expand_weight(attr.into(), item.into())
+		.unwrap_or_else(Error::into_compile_error)
+		.into()
+}
+
+#[proc_macro_attribute]
+pub fn skip(_attr: TokenStream, item: TokenStream) -> TokenStream {
+	Error::new_spanned(
+		TokenStream2::from(item),
+		"`#[skip]` can only be applied to methods of an impl block with `#[weight]`",
+	)
+	.into_compile_error()
+	.into()
+}
+
+fn expand_start(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
 	let weight: Expr = if attr.is_empty() {
-		parse((quote! { 0 }).into()).unwrap()
+		parse_quote! { ::weight_meter::Weight::zero() }
 	} else {
-		parse(attr).unwrap()
+		parse2(attr)?
 	};
 	let ItemFn {
 		attrs,
 		vis,
 		sig,
 		block,
-	} = parse(item).unwrap();
-	(quote! {
+	} = parse_fn(item, "start", "functions and methods")?;
+	// the body runs in a closure, its return type lets `return` and `?` coerce
+	// like in the function, `impl Trait` is left to inference
+	let output = match &sig.output {
+		ReturnType::Type(arrow, ty) if !matches!(**ty, Type::ImplTrait(_)) => quote! { #arrow #ty },
+		_ => quote! {},
+	};
+	Ok(quote! {
 		#(#attrs)*
 		#[cfg_attr(feature = "wasm-bench", ::wasm_bencher::benchmarkable)]
 		#vis #sig {
-			::weight_meter::start(#weight);
-			let result = #block;
-			::weight_meter::finish();
-			result
+			::weight_meter::metered(move || #output {
+				let _meter_guard = ::weight_meter::MeterGuard::start(#weight);
+				#block
+			})
 		}
 	})
-	.into()
 }
 
-#[proc_macro_attribute]
-pub fn weight(attr: TokenStream, item: TokenStream) -> TokenStream {
-	let weight: Expr = parse(attr).unwrap();
+fn expand_weight(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+	if let Ok(Item::Impl(item)) = parse2(item.clone()) {
+		return expand_weight_impl(attr, item);
+	}
+	if attr.is_empty() {
+		return Err(Error::new(
+			proc_macro2::Span::call_site(),
+			"`#[weight]` expects a weight expression, e.g. `#[weight(Weight::zero())]`",
+		));
+	}
+	let weight: Expr = parse2(attr)?;
 	let ItemFn {
 		attrs,
 		vis,
 		sig,
 		block,
-	} = parse(item).unwrap();
-	(quote! {
+	} = parse_fn(item, "weight", "functions, methods and impl blocks")?;
+	Ok(quote! {
 		#(#attrs)*
 		#[cfg_attr(feature = "wasm-bench", ::wasm_bencher::benchmarkable)]
 		#vis #sig {
@@ -45,5 +85,87 @@ pub fn weight(attr: TokenStream, item: TokenStream) -> TokenStream {
 			#block
 		}
 	})
-	.into()
+}
+
+/// Add `#[weight(<Weights>::method_name(args..))]` to every method of the impl
+/// block, `Weights` defaults to `ModuleWeights<T>` with `T` the first type
+/// parameter of the impl block. The arguments of the method are forwarded, as
+/// generated weights take the components of the bench as arguments, so methods
+/// with other arguments need their own weight. Methods with their own `#[weight]`
+/// or `#[start]` are left as they are and methods with `#[skip]` are not metered.
+fn expand_weight_impl(attr: TokenStream2, mut item: ItemImpl) -> Result<TokenStream2> {
+	let weights: Type = if attr.is_empty() {
+		match item.generics.type_params().next() {
+			Some(param) => {
+				let param = &param.ident;
+				parse_quote! { ModuleWeights<#param> }
+			},
+			None => parse_quote! { ModuleWeights },
+		}
+	} else {
+		parse2(attr)?
+	};
+	for method in item.items.iter_mut() {
+		let ImplItem::Fn(method) = method else {
+			continue;
+		};
+		let attrs_len = method.attrs.len();
+		method.attrs.retain(|attr| !is_attr(attr, "weight_meter", "skip"));
+		if method.attrs.len() != attrs_len
+			|| method.attrs.iter().any(|attr| {
+				is_attr(attr, "weight_meter", "weight") || is_attr(attr, "weight_meter", "start")
+			}) {
+			continue;
+		}
+		let name = &method.sig.ident;
+		let args = method
+			.sig
+			.inputs
+			.iter()
+			.filter_map(|arg| match arg {
+				FnArg::Receiver(_) => None,
+				FnArg::Typed(arg) => Some(arg),
+			})
+			.map(|arg| match &*arg.pat {
+				Pat::Ident(pat) => Ok(&pat.ident),
+				pat => Err(Error::new_spanned(
+					pat,
+					format!(
+						"arguments of `{name}` are forwarded to its weight and must be identifiers, add `#[weight(..)]` or `#[skip]` to the method"
+					),
+				)),
+			})
+			.collect::<Result<Vec<_>>>()?;
+		method.attrs.push(parse_quote! {
+			#[::weight_meter::weight(<#weights>::#name(#(#args),*))]
+		});
+	}
+	Ok(quote! { #item })
+}
+
+/// Whether `attr` is `#[name]` or `#[krate::name]`
+fn is_attr(attr: &Attribute, krate: &str, name: &str) -> bool {
+	let path: Vec<_> = attr.path().segments.iter().map(|segment| &segment.ident).collect();
+	match path.as_slice() {
+		[ident] => *ident == name,
+		[prefix, ident] => *prefix == krate && *ident == name,
+		_ => false,
+	}
+}
+
+/// Parse a function, or a method of an impl block, the macro `name` is applied to
+fn parse_fn(item: TokenStream2, name: &str, targets: &str) -> Result<ItemFn> {
+	let item: ItemFn = parse2(item.clone()).map_err(|_| {
+		Error::new_spanned(
+			item,
+			format!("`#[{name}]` can only be applied to {targets}"),
+		)
+	})?;
+	if let Some(asyncness) = &item.sig.asyncness {
+		return Err(Error::new(
+			asyncness.span(),
+			format!("`#[{name}]` cannot be applied to async functions"),
+		));
+	}
+	Ok(item)
 }
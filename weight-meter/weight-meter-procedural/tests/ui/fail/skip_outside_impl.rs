@@ -0,0 +1,4 @@
+#[weight_meter::skip]
+fn foo() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[weight_meter::start]
+async fn foo() {}
+
+fn main() {}
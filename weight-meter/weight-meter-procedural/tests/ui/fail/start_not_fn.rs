@@ -0,0 +1,4 @@
+#[weight_meter::start]
+const FOO: u32 = 0;
+
+fn main() {}
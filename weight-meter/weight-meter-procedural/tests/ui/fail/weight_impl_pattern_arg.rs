@@ -0,0 +1,20 @@
+use weight_meter::Weight;
+
+struct ModuleWeights;
+
+impl ModuleWeights {
+	fn set(_n: u32, _m: u32) -> Weight {
+		Weight::zero()
+	}
+}
+
+struct Value(u32);
+
+#[weight_meter::weight]
+impl Value {
+	fn set(&mut self, (n, m): (u32, u32)) {
+		self.0 = n + m;
+	}
+}
+
+fn main() {}
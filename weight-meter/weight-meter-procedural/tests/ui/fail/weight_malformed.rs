@@ -0,0 +1,4 @@
+#[weight_meter::weight(1 +)]
+fn foo() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[weight_meter::weight]
+fn foo() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[weight_meter::weight(Weight::zero())]
+struct Foo;
+
+fn main() {}
@@ -0,0 +1,64 @@
+use weight_meter::Weight;
+
+struct ModuleWeights<T>(T);
+
+impl<T> ModuleWeights<T> {
+	fn add(n: u32) -> Weight {
+		Weight::from_parts(10 * n as u64, 1)
+	}
+}
+
+struct CustomWeights;
+
+impl CustomWeights {
+	fn sub(_n: u32) -> Weight {
+		Weight::from_parts(20, 2)
+	}
+}
+
+struct Counter<T>(u32, T);
+
+#[weight_meter::weight]
+impl<T> Counter<T> {
+	#[weight_meter::start]
+	fn run(&mut self) -> Weight {
+		self.add(2);
+		self.add_one();
+		self.add_untracked();
+		weight_meter::used_weight()
+	}
+
+	fn add(&mut self, n: u32) {
+		self.0 += n;
+	}
+
+	#[weight_meter::weight(Weight::from_parts(1, 1))]
+	fn add_one(&mut self) {
+		self.0 += 1;
+	}
+
+	#[weight_meter::skip]
+	fn add_untracked(&mut self) {
+		self.0 += 1;
+	}
+}
+
+#[weight_meter::weight(CustomWeights)]
+impl<T> Counter<T> {
+	fn sub(&mut self, n: u32) {
+		self.0 -= n;
+	}
+}
+
+impl<T> Counter<T> {
+	fn get(&self) -> u32 {
+		self.0
+	}
+}
+
+fn main() {
+	let mut counter = Counter(5, ());
+	assert_eq!(counter.run(), Weight::from_parts(21, 2));
+	counter.sub(1);
+	assert_eq!(counter.get(), 8);
+}
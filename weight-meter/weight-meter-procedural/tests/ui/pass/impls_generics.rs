@@ -0,0 +1,61 @@
+use weight_meter::Weight;
+
+mod generic {
+	use weight_meter::Weight;
+
+	pub struct ModuleWeights<R>(R);
+
+	impl<R> ModuleWeights<R> {
+		pub fn set(_n: u32, _m: u32) -> Weight {
+			Weight::from_parts(10, 1)
+		}
+	}
+
+	pub struct Value<R>(pub u32, pub R);
+
+	// the weights take the first type parameter of the impl block
+	#[weight_meter::weight]
+	impl<R: Copy> Value<R> {
+		#[weight_meter::start]
+		pub fn run(&mut self) -> Weight {
+			self.set(1, 2);
+			weight_meter::used_weight()
+		}
+
+		fn set(&mut self, n: u32, m: u32) {
+			self.0 = n + m;
+		}
+	}
+}
+
+mod plain {
+	use weight_meter::Weight;
+
+	pub struct ModuleWeights;
+
+	impl ModuleWeights {
+		pub fn set(_n: u32) -> Weight {
+			Weight::from_parts(20, 2)
+		}
+	}
+
+	pub struct Value(pub u32);
+
+	#[weight_meter::weight]
+	impl Value {
+		#[weight_meter::start]
+		pub fn run(&mut self) -> Weight {
+			self.set(1);
+			weight_meter::used_weight()
+		}
+
+		fn set(&mut self, n: u32) {
+			self.0 = n;
+		}
+	}
+}
+
+fn main() {
+	assert_eq!(generic::Value(0, ()).run(), Weight::from_parts(10, 1));
+	assert_eq!(plain::Value(0).run(), Weight::from_parts(20, 2));
+}
@@ -0,0 +1,44 @@
+use weight_meter::Weight;
+
+struct Counter(u32);
+
+impl Counter {
+	#[weight_meter::start]
+	fn run(&mut self) -> Weight {
+		self.add(2);
+		weight_meter::used_weight()
+	}
+
+	// `return` and `?` behave like in the method
+	#[weight_meter::start]
+	fn value(&mut self, add: bool) -> &mut u32 {
+		if !add {
+			return &mut self.0;
+		}
+		self.add(1);
+		&mut self.0
+	}
+
+	#[weight_meter::start]
+	fn parse(s: &str) -> Result<Box<dyn core::fmt::Debug>, core::num::ParseIntError> {
+		if s.is_empty() {
+			return Ok(Box::new(()));
+		}
+		Ok(Box::new(s.parse::<u32>()?))
+	}
+
+	#[weight_meter::weight(Weight::from_parts(self.0 as u64, 1))]
+	fn add(&mut self, n: u32) {
+		self.0 += n;
+	}
+}
+
+fn main() {
+	let mut counter = Counter(5);
+	assert_eq!(counter.run(), Weight::from_parts(5, 1));
+	assert_eq!(*counter.value(false), 7);
+	*counter.value(true) += 1;
+	assert_eq!(counter.0, 9);
+	assert!(Counter::parse("x").is_err());
+	assert!(Counter::parse("").is_ok());
+}